@@ -0,0 +1,66 @@
+// Reference rust implementation of AluVM (arithmetic logic unit virtual machine).
+// To find more on AluVM please check <https://aluvm.org>
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2021-2024 by
+//     Dr Maxim Orlovsky <orlovsky@ubideco.org>
+//
+// Copyright (C) 2021-2022 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2023-2024 UBIDECO Institute. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use aluvm::corpus::{self, Script};
+use aluvm::isa::Instr;
+use aluvm::library::Lib;
+use aluvm::Vm;
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+fn decode(c: &mut Criterion) {
+    let mut group = c.benchmark_group("decode");
+    for script in corpus::load() {
+        group.bench_function(script.name, |b| {
+            b.iter(|| black_box(&script.lib).disassemble::<Instr>().unwrap())
+        });
+    }
+    group.finish();
+}
+
+fn encode(c: &mut Criterion) {
+    let mut group = c.benchmark_group("encode");
+    for script in corpus::load() {
+        let code = script.code();
+        group.bench_function(script.name, |b| b.iter(|| Lib::assemble(black_box(&code)).unwrap()));
+    }
+    group.finish();
+}
+
+fn exec(c: &mut Criterion) {
+    let mut group = c.benchmark_group("exec");
+    for script in corpus::load() {
+        let Script { name, lib } = &script;
+        let id = lib.id();
+        let entry_point = script.entry_point();
+        group.bench_function(*name, |b| {
+            b.iter(|| {
+                let mut vm = Vm::<Instr>::new();
+                assert!(vm.exec(entry_point, |lib_id| (lib_id == id).then_some(lib), &()));
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, decode, encode, exec);
+criterion_main!(benches);
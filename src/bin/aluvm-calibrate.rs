@@ -0,0 +1,245 @@
+// Reference rust implementation of AluVM (arithmetic logic unit virtual machine).
+// To find more on AluVM please check <https://aluvm.org>
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2021-2024 by
+//     Dr Maxim Orlovsky <orlovsky@ubideco.org>
+//
+// Copyright (C) 2021-2022 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2023-2024 UBIDECO Institute. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Measures execution time of each of the core opcodes on the host machine and prints a cost
+//! model table, calibrated such that the cheapest register move instructions have weight 1.
+//!
+//! Usage: `cargo run --release --features testkit --bin aluvm-calibrate -- [--rounds <N>] [--csv]`
+//!
+//! Encodings which reach operations not implemented by the interpreter yet (like conversions of
+//! tapered floats) are skipped and reported on stderr.
+
+use std::hint::black_box;
+use std::panic::{self, AssertUnwindSafe};
+use std::time::Instant;
+
+use aluvm::data::{ByteStr, Number};
+use aluvm::isa::{Bytecode, Instr, InstructionSet, OpFamily, OpcodeMap, ReservedOp};
+use aluvm::library::{Cursor, LibSeg, LibSite};
+use aluvm::reg::{CoreRegs, NumericRegister, Reg, Reg32, RegA, RegAFR, RegF, RegR};
+use aluvm::testkit::XorShift;
+use amplify::num::u5;
+
+/// Number of differently-encoded instructions sampled for each opcode.
+const SAMPLES: usize = 16;
+/// Maximal length of the instruction arguments.
+const ARGS_LEN: usize = 48;
+
+struct Measurement {
+    opcode: u8,
+    family: OpFamily,
+    mnemonic: String,
+    samples: usize,
+    nanos: f64,
+    complexity: u64,
+}
+
+fn main() {
+    let mut rounds = 10_000u32;
+    let mut csv = false;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--csv" => csv = true,
+            "--rounds" => {
+                rounds = args
+                    .next()
+                    .and_then(|n| n.parse().ok())
+                    .expect("--rounds requires a positive number")
+            }
+            _ => {
+                eprintln!("Usage: aluvm-calibrate [--rounds <N>] [--csv]");
+                std::process::exit(1);
+            }
+        }
+    }
+
+    let map = OpcodeMap::with_std().expect("inconsistent opcode map");
+    let regs = prefilled_regs();
+    let data = vec![0x5Au8; 1024];
+    let libs = LibSeg::default();
+    let mut rng = XorShift::with(0x2545_F491_4F6C_DD1D);
+
+    // Encodings hitting unimplemented parts of the interpreter (like tapered float conversions)
+    // are detected by a probe run and skipped, so silence their panic messages
+    panic::set_hook(Box::new(|_| {}));
+    let mut skipped = 0usize;
+
+    let mut measurements = vec![];
+    for opcode in 0..=u8::MAX {
+        let family = match map.family(opcode) {
+            None | Some(OpFamily::Reserved) | Some(OpFamily::Extension) => continue,
+            Some(family) => family,
+        };
+
+        let mut timings = vec![];
+        let mut sample = None;
+        for _ in 0..SAMPLES {
+            let mut code = vec![opcode];
+            code.extend((0..ARGS_LEN).map(|_| rng.next_u8()));
+            let mut reader = Cursor::with(&code[..], &data[..], &libs);
+            let instr = match Instr::<ReservedOp>::decode(&mut reader) {
+                Ok(instr) if instr.instr_byte() == opcode => instr,
+                // Instruction not supported with the current set of crate features
+                _ => continue,
+            };
+            if !is_implemented(&instr, &regs) {
+                skipped += 1;
+                continue;
+            }
+            timings.push(measure(&instr, &regs, rounds));
+            sample.get_or_insert(instr);
+        }
+        let instr = match sample {
+            Some(instr) => instr,
+            None => continue,
+        };
+        timings.sort_by(f64::total_cmp);
+        measurements.push(Measurement {
+            opcode,
+            family,
+            mnemonic: instr.to_string().split_whitespace().next().unwrap_or_default().to_owned(),
+            samples: timings.len(),
+            nanos: timings[timings.len() / 2],
+            complexity: instr.complexity(),
+        });
+    }
+
+    let _ = panic::take_hook();
+    if skipped > 0 {
+        eprintln!(
+            "Skipped {skipped} encodings using operations not implemented by the interpreter"
+        );
+    }
+
+    let baseline = measurements
+        .iter()
+        .filter(|m| m.family == OpFamily::Move)
+        .map(|m| m.nanos)
+        .fold(f64::INFINITY, f64::min);
+
+    if csv {
+        println!("opcode,family,mnemonic,samples,nanos,complexity,calibrated");
+    } else {
+        println!(
+            "| opcode | family      | mnemonic | samples |      ns | complexity | calibrated |"
+        );
+        println!(
+            "|--------|-------------|----------|---------|---------|------------|------------|"
+        );
+    }
+    for m in measurements {
+        let calibrated = (m.nanos / baseline).round().max(1.0) as u64;
+        if csv {
+            println!(
+                "{:#04X},{},{},{},{:.1},{},{}",
+                m.opcode, m.family, m.mnemonic, m.samples, m.nanos, m.complexity, calibrated
+            );
+        } else {
+            println!(
+                "| {:#04X}   | {:<11} | {:<8} | {:>7} | {:>7.1} | {:>10} | {:>10} |",
+                m.opcode,
+                m.family.to_string(),
+                m.mnemonic,
+                m.samples,
+                m.nanos,
+                m.complexity,
+                calibrated
+            );
+        }
+    }
+}
+
+/// Returns average execution time of the instruction, in nanoseconds. The instruction is executed
+/// repeatedly on the same registers, thus measuring its steady-state cost.
+/// Detects whether the instruction can be executed, i.e. doesn't reach unimplemented operations.
+fn is_implemented(instr: &Instr, regs: &CoreRegs) -> bool {
+    let mut regs = regs.clone();
+    panic::catch_unwind(AssertUnwindSafe(|| instr.exec(&mut regs, LibSite::default(), &()))).is_ok()
+}
+
+/// Measures average execution time of the instruction.
+///
+/// Each execution must start from the same register state, since otherwise repeated execution
+/// (for instance, of checked arithmetic overflowing into `None`) quickly degrades into a cheaper
+/// code path. Thus, destination registers are restored after each execution, and the time spent
+/// on restoring them is measured separately and subtracted.
+fn measure(instr: &Instr, regs: &CoreRegs, rounds: u32) -> f64 {
+    let mut numbers = vec![];
+    let mut strings = vec![];
+    for reg in instr.dst_regs() {
+        match reg {
+            Reg::A(reg, idx) => numbers.push((RegAFR::A(reg), idx, regs.get_n(reg, idx))),
+            Reg::F(reg, idx) => numbers.push((RegAFR::F(reg), idx, regs.get_n(reg, idx))),
+            Reg::R(reg, idx) => numbers.push((RegAFR::R(reg), idx, regs.get_n(reg, idx))),
+            Reg::S(reg) => strings.push((reg, regs.get_s(reg).cloned())),
+        }
+    }
+    let restore = |regs: &mut CoreRegs| {
+        for (reg, idx, value) in &numbers {
+            regs.set_n(*reg, *idx, *value);
+        }
+        for (reg, value) in &strings {
+            regs.set_s(*reg, value.clone());
+        }
+    };
+
+    let mut regs = regs.clone();
+    let site = LibSite::default();
+    let start = Instant::now();
+    for _ in 0..rounds {
+        black_box(instr.exec(black_box(&mut regs), site, &()));
+        restore(&mut regs);
+    }
+    let total = start.elapsed();
+    let start = Instant::now();
+    for _ in 0..rounds {
+        restore(black_box(&mut regs));
+    }
+    let overhead = start.elapsed();
+    total.saturating_sub(overhead).as_nanos() as f64 / rounds as f64
+}
+
+fn prefilled_regs() -> CoreRegs {
+    let mut regs = CoreRegs::default();
+    for idx in 0..32u8 {
+        let idx = Reg32::from(u5::with(idx));
+        for reg in RegA::ALL {
+            regs.set_n(reg, idx, Number::from_slice(vec![0x5A; reg.bytes() as usize]));
+        }
+        for reg in RegF::ALL {
+            regs.set_n(
+                reg,
+                idx,
+                Number::with(vec![0x3C; reg.bytes() as usize], reg.layout()).as_ref(),
+            );
+        }
+        for reg in RegR::ALL {
+            regs.set_n(reg, idx, Number::from_slice(vec![0xA5; reg.bytes() as usize]));
+        }
+    }
+    for idx in 0..16u8 {
+        regs.set_s(idx, Some(ByteStr::with([0x5Au8; 256])));
+    }
+    regs
+}
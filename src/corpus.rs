@@ -0,0 +1,186 @@
+// Reference rust implementation of AluVM (arithmetic logic unit virtual machine).
+// To find more on AluVM please check <https://aluvm.org>
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2021-2024 by
+//     Dr Maxim Orlovsky <orlovsky@ubideco.org>
+//
+// Copyright (C) 2021-2022 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2023-2024 UBIDECO Institute. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Corpus of representative AluVM scripts, used for benchmarking and regression testing.
+//!
+//! The scripts mimic the shape of typical validation code: tight arithmetic loops, hashing of
+//! string data and (with `secp256k1` feature) elliptic curve operations. Each script completes
+//! with `st0` set to `true`.
+
+use crate::data::{ByteStr, MaybeNumber, Number, Step};
+use crate::isa::{
+    ArithmeticOp, BytesOp, CmpOp, ControlFlowOp, DigestOp, Instr, IntFlags, PutOp, SignFlag,
+};
+use crate::library::{Lib, LibSite};
+use crate::reg::{Reg16, Reg32, RegA, RegS};
+
+/// Number of loop iterations performed by each of the corpus scripts.
+pub const ITERATIONS: u64 = 1000;
+
+/// Script from the bundled corpus
+#[derive(Clone, Debug)]
+pub struct Script {
+    /// Name of the script
+    pub name: &'static str,
+    /// Library containing the script code
+    pub lib: Lib,
+}
+
+impl Script {
+    /// Returns entry point of the script
+    #[inline]
+    pub fn entry_point(&self) -> LibSite { LibSite::with(0, self.lib.id()) }
+
+    /// Returns instructions of the script
+    pub fn code(&self) -> Vec<Instr> {
+        self.lib.disassemble().expect("corpus scripts are always decodable")
+    }
+}
+
+/// Loads all scripts from the bundled corpus.
+pub fn load() -> Vec<Script> {
+    #[cfg_attr(not(feature = "secp256k1"), allow(unused_mut))]
+    let mut corpus = vec![
+        Script { name: "counter", lib: counter() },
+        Script { name: "arithmetic", lib: arithmetic() },
+        Script { name: "hashing", lib: hashing() },
+    ];
+    #[cfg(feature = "secp256k1")]
+    corpus.push(Script { name: "secp256k1", lib: secp256k1() });
+    corpus
+}
+
+/// Loads script with a given name from the bundled corpus.
+pub fn script(name: &str) -> Option<Script> {
+    load().into_iter().find(|script| script.name == name)
+}
+
+/// Assembles a library executing `body` [`ITERATIONS`] times after the `prelude`. The loop
+/// counter uses `a64[30]` and `a64[31]` registers, which must not be used by the provided code.
+fn looped(prelude: Vec<Instr>, body: Vec<Instr>) -> Lib {
+    let mut code = prelude;
+    code.push(Instr::Put(PutOp::PutA(
+        RegA::A64,
+        Reg32::Reg30,
+        Box::new(MaybeNumber::from(Number::from(0u64))),
+    )));
+    code.push(Instr::Put(PutOp::PutA(
+        RegA::A64,
+        Reg32::Reg31,
+        Box::new(MaybeNumber::from(Number::from(ITERATIONS))),
+    )));
+    let start = Lib::assemble(&code).expect("corpus scripts are always valid").code.len();
+
+    code.extend(body);
+    code.push(Instr::Arithmetic(ArithmeticOp::Stp(RegA::A64, Reg32::Reg30, Step::with(1))));
+    code.push(Instr::Cmp(CmpOp::LtA(SignFlag::Unsigned, RegA::A64, Reg32::Reg30, Reg32::Reg31)));
+    code.push(Instr::ControlFlow(ControlFlowOp::Jif(start as u16)));
+    // Loop exits once the comparison fails, so the status must be restored
+    code.push(Instr::Cmp(CmpOp::StInv));
+    code.push(Instr::ControlFlow(ControlFlowOp::Ret));
+    Lib::assemble(&code).expect("corpus scripts are always valid")
+}
+
+fn counter() -> Lib { looped(vec![], vec![]) }
+
+fn arithmetic() -> Lib {
+    let put = |reg, idx, val: u64| {
+        Instr::Put(PutOp::PutA(reg, idx, Box::new(MaybeNumber::from(Number::from(val)))))
+    };
+    let prelude = vec![
+        put(RegA::A128, Reg32::Reg0, 0x0123_4567_89AB_CDEF),
+        put(RegA::A128, Reg32::Reg1, 0xFEDC_BA98_7654_3210),
+        put(RegA::A128, Reg32::Reg2, 7),
+    ];
+    let body = vec![
+        Instr::Arithmetic(ArithmeticOp::AddA(
+            IntFlags::unsigned_wrapped(),
+            RegA::A128,
+            Reg32::Reg1,
+            Reg32::Reg0,
+        )),
+        Instr::Arithmetic(ArithmeticOp::MulA(
+            IntFlags::unsigned_wrapped(),
+            RegA::A128,
+            Reg32::Reg1,
+            Reg32::Reg0,
+        )),
+        Instr::Arithmetic(ArithmeticOp::DivA(
+            IntFlags::unsigned_checked(),
+            RegA::A128,
+            Reg32::Reg2,
+            Reg32::Reg0,
+        )),
+    ];
+    looped(prelude, body)
+}
+
+fn hashing() -> Lib {
+    let data = (0..1024u16).map(|i| i as u8).collect::<Vec<_>>();
+    let prelude =
+        vec![Instr::Bytes(BytesOp::Put(RegS::from(0u8), Box::new(ByteStr::with(data)), false))];
+    let body = vec![
+        Instr::Digest(DigestOp::Sha256(RegS::from(0u8), Reg16::Reg0)),
+        Instr::Digest(DigestOp::Ripemd(RegS::from(0u8), Reg16::Reg0)),
+    ];
+    looped(prelude, body)
+}
+
+#[cfg(feature = "secp256k1")]
+fn secp256k1() -> Lib {
+    use crate::isa::Secp256k1Op;
+    use crate::reg::{Reg8, RegR};
+
+    let prelude = vec![Instr::Put(PutOp::PutR(
+        RegR::R256,
+        Reg32::Reg0,
+        Box::new(MaybeNumber::from(Number::from([0x5Au8; 32]))),
+    ))];
+    let body = vec![Instr::Secp256k1(Secp256k1Op::Gen(Reg32::Reg0, Reg8::Reg0))];
+    looped(prelude, body)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Vm;
+
+    #[test]
+    fn corpus_succeeds() {
+        for script in load() {
+            let mut vm = Vm::<Instr>::new();
+            let id = script.lib.id();
+            let lib = &script.lib;
+            assert!(
+                vm.exec(script.entry_point(), |lib_id| (lib_id == id).then_some(lib), &()),
+                "script {} failed",
+                script.name
+            );
+            assert!(
+                vm.registers.instr_count() > ITERATIONS * 3,
+                "script {} is too short",
+                script.name
+            );
+        }
+    }
+}
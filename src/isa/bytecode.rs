@@ -25,14 +25,15 @@
 
 #[cfg(all(feature = "alloc", not(feature = "std")))]
 use alloc::boxed::Box;
+use core::marker::PhantomData;
 use core::ops::RangeInclusive;
 
 use amplify::num::{u1, u2, u3, u5};
 
 use super::opcodes::*;
 use super::{
-    ArithmeticOp, BitwiseOp, BytesOp, CmpOp, ControlFlowOp, Curve25519Op, DigestOp, Instr,
-    InstructionSet, MoveOp, PutOp, ReservedOp, Secp256k1Op,
+    ArithmeticOp, BitwiseOp, BytesOp, CmpOp, ConstOp, ControlFlowOp, Curve25519Op, DigestOp, Instr,
+    InstructionSet, IntrospectOp, MoveOp, PutOp, ReservedOp, Secp256k1Op,
 };
 use crate::data::{ByteStr, MaybeNumber};
 use crate::library::{CodeEofError, LibSite, Read, Write, WriteError};
@@ -99,6 +100,373 @@ pub trait Bytecode {
         R: Read;
 }
 
+/// Defines opcode range of a core instruction family, which is returned by its
+/// [`Bytecode::instr_range`] and is used to build the decoding and execution dispatch tables.
+macro_rules! instr_range {
+    ($op:ty, $range:expr) => {
+        impl $op {
+            pub(crate) const INSTR_RANGE: RangeInclusive<u8> = $range;
+        }
+    };
+}
+
+instr_range!(ControlFlowOp, INSTR_FAIL..=INSTR_RET);
+instr_range!(PutOp, INSTR_CLRA..=INSTR_PUTIFR);
+instr_range!(MoveOp, INSTR_MOV..=INSTR_CFA);
+instr_range!(CmpOp, INSTR_LGT..=INSTR_STINV);
+instr_range!(ArithmeticOp, INSTR_ADD..=INSTR_REM);
+instr_range!(BitwiseOp, INSTR_AND..=INSTR_REVR);
+instr_range!(BytesOp, INSTR_PUT..=INSTR_REV);
+instr_range!(ConstOp, INSTR_ZEROA..=INSTR_MAXR);
+instr_range!(IntrospectOp, INSTR_SITE..=INSTR_ICNT);
+instr_range!(DigestOp, INSTR_RIPEMD..=INSTR_SHA512);
+instr_range!(Secp256k1Op, INSTR_SECP_GEN..=INSTR_SECP_NEG);
+instr_range!(Curve25519Op, INSTR_ED_GEN..=INSTR_ED_NEG);
+
+pub(crate) mod dispatch {
+    // With `match-dispatch` feature the tables are used only by the tests comparing both paths
+    #![cfg_attr(all(feature = "match-dispatch", not(test)), allow(dead_code))]
+
+    use super::*;
+    use crate::isa::ExecStep;
+    use crate::reg::CoreRegs;
+
+    /// Function decoding instruction of a specific family, used in [`Dispatch::DECODE`] table.
+    type DecodeFn<Extension, R> = fn(&mut R) -> Result<Instr<Extension>, CodeEofError>;
+
+    /// Function decoding and executing instruction of a specific family, used in
+    /// [`Dispatch::STEP`] table.
+    type StepFn<Extension, R> = fn(
+        &mut R,
+        &mut CoreRegs,
+        LibSite,
+        &<Extension as InstructionSet>::Context<'_>,
+    ) -> Option<ExecStep>;
+
+    /// Performs single step of the program execution with an already decoded instruction; see
+    /// [`InstructionSet::step`].
+    #[inline]
+    pub(crate) fn step_with<Isa>(
+        instr: Isa,
+        regs: &mut CoreRegs,
+        site: LibSite,
+        context: &Isa::Context<'_>,
+    ) -> Option<ExecStep>
+    where
+        Isa: InstructionSet,
+    {
+        if !regs.check_src_regs(&instr) {
+            return None;
+        }
+        let next = instr.exec(regs, site, context);
+        if !regs.acc_complexity(instr) {
+            return None;
+        }
+        Some(next)
+    }
+
+    macro_rules! family_fn {
+        ($decode:ident, $step:ident, $variant:ident, $op:ty) => {
+            fn $decode<Extension, R>(reader: &mut R) -> Result<Instr<Extension>, CodeEofError>
+            where
+                Extension: InstructionSet,
+                R: Read,
+            {
+                Ok(Instr::$variant(<$op>::decode(reader)?))
+            }
+
+            fn $step<Extension, R>(
+                reader: &mut R,
+                regs: &mut CoreRegs,
+                site: LibSite,
+                _: &Extension::Context<'_>,
+            ) -> Option<ExecStep>
+            where
+                Extension: InstructionSet,
+                R: Read,
+            {
+                step_with(<$op>::decode(reader).ok()?, regs, site, &())
+            }
+        };
+    }
+
+    family_fn!(decode_control_flow, step_control_flow, ControlFlow, ControlFlowOp);
+    family_fn!(decode_put, step_put, Put, PutOp);
+    family_fn!(decode_move, step_move, Move, MoveOp);
+    family_fn!(decode_cmp, step_cmp, Cmp, CmpOp);
+    family_fn!(decode_arithmetic, step_arithmetic, Arithmetic, ArithmeticOp);
+    family_fn!(decode_bitwise, step_bitwise, Bitwise, BitwiseOp);
+    family_fn!(decode_bytes, step_bytes, Bytes, BytesOp);
+    family_fn!(decode_const, step_const, Const, ConstOp);
+    family_fn!(decode_introspect, step_introspect, Introspect, IntrospectOp);
+    family_fn!(decode_digest, step_digest, Digest, DigestOp);
+    #[cfg(feature = "secp256k1")]
+    family_fn!(decode_secp256k1, step_secp256k1, Secp256k1, Secp256k1Op);
+    #[cfg(feature = "curve25519")]
+    family_fn!(decode_curve25519, step_curve25519, Curve25519, Curve25519Op);
+
+    fn decode_reserved<Extension, R>(reader: &mut R) -> Result<Instr<Extension>, CodeEofError>
+    where
+        Extension: InstructionSet,
+        R: Read,
+    {
+        Ok(Instr::ReservedInstruction(ReservedOp::decode(reader)?))
+    }
+
+    fn step_reserved<Extension, R>(
+        reader: &mut R,
+        regs: &mut CoreRegs,
+        site: LibSite,
+        context: &Extension::Context<'_>,
+    ) -> Option<ExecStep>
+    where
+        Extension: InstructionSet,
+        R: Read,
+    {
+        let instr = decode_reserved::<Extension, R>(reader).ok()?;
+        step_with(instr, regs, site, context)
+    }
+
+    fn decode_nop<Extension, R>(reader: &mut R) -> Result<Instr<Extension>, CodeEofError>
+    where
+        Extension: InstructionSet,
+        R: Read,
+    {
+        reader.read_u8()?;
+        Ok(Instr::Nop)
+    }
+
+    fn step_nop<Extension, R>(
+        reader: &mut R,
+        regs: &mut CoreRegs,
+        site: LibSite,
+        context: &Extension::Context<'_>,
+    ) -> Option<ExecStep>
+    where
+        Extension: InstructionSet,
+        R: Read,
+    {
+        let instr = decode_nop::<Extension, R>(reader).ok()?;
+        step_with(instr, regs, site, context)
+    }
+
+    fn decode_extension<Extension, R>(reader: &mut R) -> Result<Instr<Extension>, CodeEofError>
+    where
+        Extension: InstructionSet,
+        R: Read,
+    {
+        Ok(Instr::ExtensionCodes(Extension::decode(reader)?))
+    }
+
+    fn step_extension<Extension, R>(
+        reader: &mut R,
+        regs: &mut CoreRegs,
+        site: LibSite,
+        context: &Extension::Context<'_>,
+    ) -> Option<ExecStep>
+    where
+        Extension: InstructionSet,
+        R: Read,
+    {
+        Extension::step(reader, regs, site, context)
+    }
+
+    /// Instruction family handlers for a given opcode
+    enum Family {
+        ControlFlow,
+        Put,
+        Move,
+        Cmp,
+        Arithmetic,
+        Bitwise,
+        Bytes,
+        Const,
+        Introspect,
+        Digest,
+        #[cfg(feature = "secp256k1")]
+        Secp256k1,
+        #[cfg(feature = "curve25519")]
+        Curve25519,
+        Reserved,
+        Nop,
+        Extension,
+    }
+
+    impl Family {
+        const fn of(opcode: u8) -> Family {
+            const fn within(opcode: u8, range: &RangeInclusive<u8>) -> bool {
+                *range.start() <= opcode && opcode <= *range.end()
+            }
+
+            if within(opcode, &ControlFlowOp::INSTR_RANGE) {
+                Family::ControlFlow
+            } else if within(opcode, &PutOp::INSTR_RANGE) {
+                Family::Put
+            } else if within(opcode, &MoveOp::INSTR_RANGE) {
+                Family::Move
+            } else if within(opcode, &CmpOp::INSTR_RANGE) {
+                Family::Cmp
+            } else if within(opcode, &ArithmeticOp::INSTR_RANGE) {
+                Family::Arithmetic
+            } else if within(opcode, &BitwiseOp::INSTR_RANGE) {
+                Family::Bitwise
+            } else if within(opcode, &BytesOp::INSTR_RANGE) {
+                Family::Bytes
+            } else if within(opcode, &ConstOp::INSTR_RANGE) {
+                Family::Const
+            } else if within(opcode, &IntrospectOp::INSTR_RANGE) {
+                Family::Introspect
+            } else if within(opcode, &DigestOp::INSTR_RANGE) {
+                Family::Digest
+            } else if cfg!(feature = "secp256k1") && within(opcode, &Secp256k1Op::INSTR_RANGE) {
+                #[cfg(feature = "secp256k1")]
+                return Family::Secp256k1;
+                #[cfg(not(feature = "secp256k1"))]
+                unreachable!()
+            } else if cfg!(feature = "curve25519") && within(opcode, &Curve25519Op::INSTR_RANGE) {
+                #[cfg(feature = "curve25519")]
+                return Family::Curve25519;
+                #[cfg(not(feature = "curve25519"))]
+                unreachable!()
+            } else if within(opcode, &(INSTR_RESV_FROM..=INSTR_RESV_TO)) {
+                Family::Reserved
+            } else if opcode == INSTR_NOP {
+                Family::Nop
+            } else {
+                Family::Extension
+            }
+        }
+    }
+
+    /// Opcode-indexed tables of instruction handlers, replacing sequential matching of the opcode
+    /// against instruction family ranges with a single indirect call. The tables are built at
+    /// compile time from the same opcode ranges which are returned by [`Bytecode::instr_range`].
+    pub(crate) struct Dispatch<Extension, R>(PhantomData<(Extension, R)>);
+
+    impl<Extension, R> Dispatch<Extension, R>
+    where
+        Extension: InstructionSet,
+        R: Read,
+    {
+        /// Decoders of instructions, indexed by their opcode
+        pub(crate) const DECODE: [DecodeFn<Extension, R>; 256] = {
+            let mut table: [DecodeFn<Extension, R>; 256] =
+                [decode_extension::<Extension, R> as DecodeFn<Extension, R>; 256];
+            let mut opcode = 0usize;
+            while opcode < table.len() {
+                table[opcode] = match Family::of(opcode as u8) {
+                    Family::ControlFlow => decode_control_flow::<Extension, R>,
+                    Family::Put => decode_put::<Extension, R>,
+                    Family::Move => decode_move::<Extension, R>,
+                    Family::Cmp => decode_cmp::<Extension, R>,
+                    Family::Arithmetic => decode_arithmetic::<Extension, R>,
+                    Family::Bitwise => decode_bitwise::<Extension, R>,
+                    Family::Bytes => decode_bytes::<Extension, R>,
+                    Family::Const => decode_const::<Extension, R>,
+                    Family::Introspect => decode_introspect::<Extension, R>,
+                    Family::Digest => decode_digest::<Extension, R>,
+                    #[cfg(feature = "secp256k1")]
+                    Family::Secp256k1 => decode_secp256k1::<Extension, R>,
+                    #[cfg(feature = "curve25519")]
+                    Family::Curve25519 => decode_curve25519::<Extension, R>,
+                    Family::Reserved => decode_reserved::<Extension, R>,
+                    Family::Nop => decode_nop::<Extension, R>,
+                    Family::Extension => decode_extension::<Extension, R>,
+                };
+                opcode += 1;
+            }
+            table
+        };
+
+        /// Handlers decoding and executing instructions, indexed by their opcode
+        pub(crate) const STEP: [StepFn<Extension, R>; 256] = {
+            let mut table: [StepFn<Extension, R>; 256] =
+                [step_extension::<Extension, R> as StepFn<Extension, R>; 256];
+            let mut opcode = 0usize;
+            while opcode < table.len() {
+                table[opcode] = match Family::of(opcode as u8) {
+                    Family::ControlFlow => step_control_flow::<Extension, R>,
+                    Family::Put => step_put::<Extension, R>,
+                    Family::Move => step_move::<Extension, R>,
+                    Family::Cmp => step_cmp::<Extension, R>,
+                    Family::Arithmetic => step_arithmetic::<Extension, R>,
+                    Family::Bitwise => step_bitwise::<Extension, R>,
+                    Family::Bytes => step_bytes::<Extension, R>,
+                    Family::Const => step_const::<Extension, R>,
+                    Family::Introspect => step_introspect::<Extension, R>,
+                    Family::Digest => step_digest::<Extension, R>,
+                    #[cfg(feature = "secp256k1")]
+                    Family::Secp256k1 => step_secp256k1::<Extension, R>,
+                    #[cfg(feature = "curve25519")]
+                    Family::Curve25519 => step_curve25519::<Extension, R>,
+                    Family::Reserved => step_reserved::<Extension, R>,
+                    Family::Nop => step_nop::<Extension, R>,
+                    Family::Extension => step_extension::<Extension, R>,
+                };
+                opcode += 1;
+            }
+            table
+        };
+    }
+
+    /// Decodes instruction by sequentially matching its opcode against the ranges of the
+    /// instruction families. Used instead of [`Dispatch::DECODE`] table with `match-dispatch`
+    /// feature.
+    #[cfg(any(test, feature = "match-dispatch"))]
+    pub(crate) fn decode_by_ranges<Extension, R>(
+        reader: &mut R,
+    ) -> Result<Instr<Extension>, CodeEofError>
+    where
+        Extension: InstructionSet,
+        R: Read,
+    {
+        let instr = reader.peek_u8()?;
+        Ok(match instr {
+            instr if ControlFlowOp::instr_range().contains(&instr) => {
+                Instr::ControlFlow(ControlFlowOp::decode(reader)?)
+            }
+            instr if PutOp::instr_range().contains(&instr) => Instr::Put(PutOp::decode(reader)?),
+            instr if MoveOp::instr_range().contains(&instr) => Instr::Move(MoveOp::decode(reader)?),
+            instr if CmpOp::instr_range().contains(&instr) => Instr::Cmp(CmpOp::decode(reader)?),
+            instr if ArithmeticOp::instr_range().contains(&instr) => {
+                Instr::Arithmetic(ArithmeticOp::decode(reader)?)
+            }
+            instr if BitwiseOp::instr_range().contains(&instr) => {
+                Instr::Bitwise(BitwiseOp::decode(reader)?)
+            }
+            instr if BytesOp::instr_range().contains(&instr) => {
+                Instr::Bytes(BytesOp::decode(reader)?)
+            }
+            instr if ConstOp::instr_range().contains(&instr) => {
+                Instr::Const(ConstOp::decode(reader)?)
+            }
+            instr if IntrospectOp::instr_range().contains(&instr) => {
+                Instr::Introspect(IntrospectOp::decode(reader)?)
+            }
+            instr if DigestOp::instr_range().contains(&instr) => {
+                Instr::Digest(DigestOp::decode(reader)?)
+            }
+            #[cfg(feature = "secp256k1")]
+            instr if Secp256k1Op::instr_range().contains(&instr) => {
+                Instr::Secp256k1(Secp256k1Op::decode(reader)?)
+            }
+            #[cfg(feature = "curve25519")]
+            instr if Curve25519Op::instr_range().contains(&instr) => {
+                Instr::Curve25519(Curve25519Op::decode(reader)?)
+            }
+            INSTR_RESV_FROM..=INSTR_RESV_TO => {
+                Instr::ReservedInstruction(ReservedOp::decode(reader)?)
+            }
+            INSTR_NOP => {
+                reader.read_u8()?;
+                Instr::Nop
+            }
+            INSTR_ISAE_FROM..=INSTR_ISAE_TO => Instr::ExtensionCodes(Extension::decode(reader)?),
+            x => unreachable!("unable to classify instruction {:#010b}", x),
+        })
+    }
+}
+
 impl<Extension> Bytecode for Instr<Extension>
 where
     Extension: InstructionSet,
@@ -115,6 +483,8 @@ where
             Instr::Arithmetic(instr) => instr.instr_byte(),
             Instr::Bitwise(instr) => instr.instr_byte(),
             Instr::Bytes(instr) => instr.instr_byte(),
+            Instr::Const(instr) => instr.instr_byte(),
+            Instr::Introspect(instr) => instr.instr_byte(),
             Instr::Digest(instr) => instr.instr_byte(),
             #[cfg(feature = "secp256k1")]
             Instr::Secp256k1(instr) => instr.instr_byte(),
@@ -135,6 +505,8 @@ where
             Instr::Arithmetic(instr) => instr.call_site(),
             Instr::Bitwise(instr) => instr.call_site(),
             Instr::Bytes(instr) => instr.call_site(),
+            Instr::Const(instr) => instr.call_site(),
+            Instr::Introspect(instr) => instr.call_site(),
             Instr::Digest(instr) => instr.call_site(),
             #[cfg(feature = "secp256k1")]
             Instr::Secp256k1(instr) => instr.call_site(),
@@ -158,6 +530,8 @@ where
             Instr::Arithmetic(instr) => instr.encode_args(writer),
             Instr::Bitwise(instr) => instr.encode_args(writer),
             Instr::Bytes(instr) => instr.encode_args(writer),
+            Instr::Const(instr) => instr.encode_args(writer),
+            Instr::Introspect(instr) => instr.encode_args(writer),
             Instr::Digest(instr) => instr.encode_args(writer),
             #[cfg(feature = "secp256k1")]
             Instr::Secp256k1(instr) => instr.encode_args(writer),
@@ -173,47 +547,23 @@ where
     where
         R: Read,
     {
-        let instr = reader.peek_u8()?;
-        Ok(match instr {
-            instr if ControlFlowOp::instr_range().contains(&instr) => {
-                Instr::ControlFlow(ControlFlowOp::decode(reader)?)
-            }
-            instr if PutOp::instr_range().contains(&instr) => Instr::Put(PutOp::decode(reader)?),
-            instr if MoveOp::instr_range().contains(&instr) => Instr::Move(MoveOp::decode(reader)?),
-            instr if CmpOp::instr_range().contains(&instr) => Instr::Cmp(CmpOp::decode(reader)?),
-            instr if ArithmeticOp::instr_range().contains(&instr) => {
-                Instr::Arithmetic(ArithmeticOp::decode(reader)?)
-            }
-            instr if BitwiseOp::instr_range().contains(&instr) => {
-                Instr::Bitwise(BitwiseOp::decode(reader)?)
-            }
-            instr if BytesOp::instr_range().contains(&instr) => {
-                Instr::Bytes(BytesOp::decode(reader)?)
-            }
-            instr if DigestOp::instr_range().contains(&instr) => {
-                Instr::Digest(DigestOp::decode(reader)?)
-            }
-            #[cfg(feature = "secp256k1")]
-            instr if Secp256k1Op::instr_range().contains(&instr) => {
-                Instr::Secp256k1(Secp256k1Op::decode(reader)?)
-            }
-            #[cfg(feature = "curve25519")]
-            instr if Curve25519Op::instr_range().contains(&instr) => {
-                Instr::Curve25519(Curve25519Op::decode(reader)?)
-            }
-            INSTR_RESV_FROM..=INSTR_RESV_TO => {
-                Instr::ReservedInstruction(ReservedOp::decode(reader)?)
-            }
-            INSTR_NOP => Instr::Nop,
-            INSTR_ISAE_FROM..=INSTR_ISAE_TO => Instr::ExtensionCodes(Extension::decode(reader)?),
-            x => unreachable!("unable to classify instruction {:#010b}", x),
-        })
+        #[cfg(not(feature = "match-dispatch"))]
+        {
+            let opcode = reader.peek_u8()?;
+            let table = &dispatch::Dispatch::<Extension, R>::DECODE;
+            table[opcode as usize](reader)
+        }
+
+        #[cfg(feature = "match-dispatch")]
+        {
+            dispatch::decode_by_ranges(reader)
+        }
     }
 }
 
 impl Bytecode for ControlFlowOp {
     #[inline]
-    fn instr_range() -> RangeInclusive<u8> { INSTR_FAIL..=INSTR_RET }
+    fn instr_range() -> RangeInclusive<u8> { Self::INSTR_RANGE }
 
     fn instr_byte(&self) -> u8 {
         match self {
@@ -275,7 +625,7 @@ impl Bytecode for ControlFlowOp {
 
 impl Bytecode for PutOp {
     #[inline]
-    fn instr_range() -> RangeInclusive<u8> { INSTR_CLRA..=INSTR_PUTIFR }
+    fn instr_range() -> RangeInclusive<u8> { Self::INSTR_RANGE }
 
     fn instr_byte(&self) -> u8 {
         match self {
@@ -393,7 +743,7 @@ impl Bytecode for PutOp {
 
 impl Bytecode for MoveOp {
     #[inline]
-    fn instr_range() -> RangeInclusive<u8> { INSTR_MOV..=INSTR_CFA }
+    fn instr_range() -> RangeInclusive<u8> { Self::INSTR_RANGE }
 
     fn instr_byte(&self) -> u8 {
         match self {
@@ -557,7 +907,7 @@ impl Bytecode for MoveOp {
 
 impl Bytecode for CmpOp {
     #[inline]
-    fn instr_range() -> RangeInclusive<u8> { INSTR_LGT..=INSTR_STINV }
+    fn instr_range() -> RangeInclusive<u8> { Self::INSTR_RANGE }
 
     fn instr_byte(&self) -> u8 {
         match self {
@@ -711,7 +1061,7 @@ impl Bytecode for CmpOp {
 
 impl Bytecode for ArithmeticOp {
     #[inline]
-    fn instr_range() -> RangeInclusive<u8> { INSTR_ADD..=INSTR_REM }
+    fn instr_range() -> RangeInclusive<u8> { Self::INSTR_RANGE }
 
     fn instr_byte(&self) -> u8 {
         match self {
@@ -818,7 +1168,7 @@ impl Bytecode for ArithmeticOp {
 
 impl Bytecode for BitwiseOp {
     #[inline]
-    fn instr_range() -> RangeInclusive<u8> { INSTR_AND..=INSTR_REVR }
+    fn instr_range() -> RangeInclusive<u8> { Self::INSTR_RANGE }
 
     fn instr_byte(&self) -> u8 {
         match self {
@@ -974,7 +1324,7 @@ impl Bytecode for BitwiseOp {
 
 impl Bytecode for BytesOp {
     #[inline]
-    fn instr_range() -> RangeInclusive<u8> { INSTR_PUT..=INSTR_REV }
+    fn instr_range() -> RangeInclusive<u8> { Self::INSTR_RANGE }
 
     fn instr_byte(&self) -> u8 {
         match self {
@@ -1171,9 +1521,112 @@ impl Bytecode for BytesOp {
     }
 }
 
+impl Bytecode for ConstOp {
+    #[inline]
+    fn instr_range() -> RangeInclusive<u8> { Self::INSTR_RANGE }
+
+    fn instr_byte(&self) -> u8 {
+        match self {
+            ConstOp::ZeroA(_, _) => INSTR_ZEROA,
+            ConstOp::ZeroF(_, _) => INSTR_ZEROF,
+            ConstOp::ZeroR(_, _) => INSTR_ZEROR,
+            ConstOp::OneA(_, _) => INSTR_ONEA,
+            ConstOp::OneF(_, _) => INSTR_ONEF,
+            ConstOp::OneR(_, _) => INSTR_ONER,
+            ConstOp::MaxA(_, _) => INSTR_MAXA,
+            ConstOp::MaxR(_, _) => INSTR_MAXR,
+        }
+    }
+
+    fn encode_args<W>(&self, writer: &mut W) -> Result<(), BytecodeError>
+    where
+        W: Write,
+    {
+        let (reg, idx): (u3, _) = match self {
+            ConstOp::ZeroA(reg, idx) | ConstOp::OneA(reg, idx) | ConstOp::MaxA(reg, idx) => {
+                (reg.into(), idx)
+            }
+            ConstOp::ZeroF(reg, idx) | ConstOp::OneF(reg, idx) => (reg.into(), idx),
+            ConstOp::ZeroR(reg, idx) | ConstOp::OneR(reg, idx) | ConstOp::MaxR(reg, idx) => {
+                (reg.into(), idx)
+            }
+        };
+        writer.write_u3(reg)?;
+        writer.write_u5(idx)?;
+        Ok(())
+    }
+
+    fn decode<R>(reader: &mut R) -> Result<Self, CodeEofError>
+    where
+        R: Read,
+    {
+        let instr = reader.read_u8()?;
+        let reg = reader.read_u3()?;
+        let index = reader.read_u5()?.into();
+
+        Ok(match instr {
+            INSTR_ZEROA => Self::ZeroA(reg.into(), index),
+            INSTR_ZEROF => Self::ZeroF(reg.into(), index),
+            INSTR_ZEROR => Self::ZeroR(reg.into(), index),
+            INSTR_ONEA => Self::OneA(reg.into(), index),
+            INSTR_ONEF => Self::OneF(reg.into(), index),
+            INSTR_ONER => Self::OneR(reg.into(), index),
+            INSTR_MAXA => Self::MaxA(reg.into(), index),
+            INSTR_MAXR => Self::MaxR(reg.into(), index),
+            x => unreachable!("instruction {:#010b} classified as constant load operation", x),
+        })
+    }
+}
+
+impl Bytecode for IntrospectOp {
+    #[inline]
+    fn instr_range() -> RangeInclusive<u8> { Self::INSTR_RANGE }
+
+    fn instr_byte(&self) -> u8 {
+        match self {
+            IntrospectOp::Site(_, _) => INSTR_SITE,
+            IntrospectOp::Icnt(_) => INSTR_ICNT,
+        }
+    }
+
+    fn encode_args<W>(&self, writer: &mut W) -> Result<(), BytecodeError>
+    where
+        W: Write,
+    {
+        match self {
+            IntrospectOp::Site(id, pos) => {
+                writer.write_u4(id)?;
+                writer.write_u4(pos)?;
+            }
+            IntrospectOp::Icnt(dst) => {
+                writer.write_u5(dst)?;
+                writer.write_u3(u3::with(0))?;
+            }
+        }
+        Ok(())
+    }
+
+    fn decode<R>(reader: &mut R) -> Result<Self, CodeEofError>
+    where
+        R: Read,
+    {
+        let instr = reader.read_u8()?;
+
+        Ok(match instr {
+            INSTR_SITE => Self::Site(reader.read_u4()?.into(), reader.read_u4()?.into()),
+            INSTR_ICNT => {
+                let dst = reader.read_u5()?.into();
+                let _ = reader.read_u3()?;
+                Self::Icnt(dst)
+            }
+            x => unreachable!("instruction {:#010b} classified as introspection operation", x),
+        })
+    }
+}
+
 impl Bytecode for DigestOp {
     #[inline]
-    fn instr_range() -> RangeInclusive<u8> { INSTR_RIPEMD..=INSTR_SHA512 }
+    fn instr_range() -> RangeInclusive<u8> { Self::INSTR_RANGE }
 
     fn instr_byte(&self) -> u8 {
         match self {
@@ -1220,7 +1673,7 @@ impl Bytecode for DigestOp {
 
 impl Bytecode for Secp256k1Op {
     #[inline]
-    fn instr_range() -> RangeInclusive<u8> { INSTR_SECP_GEN..=INSTR_SECP_NEG }
+    fn instr_range() -> RangeInclusive<u8> { Self::INSTR_RANGE }
 
     fn instr_byte(&self) -> u8 {
         match self {
@@ -1279,7 +1732,7 @@ impl Bytecode for Secp256k1Op {
 
 impl Bytecode for Curve25519Op {
     #[inline]
-    fn instr_range() -> RangeInclusive<u8> { INSTR_ED_GEN..=INSTR_ED_NEG }
+    fn instr_range() -> RangeInclusive<u8> { Self::INSTR_RANGE }
 
     fn instr_byte(&self) -> u8 {
         match self {
@@ -1366,3 +1819,109 @@ impl Bytecode for ReservedOp {
         Ok(ReservedOp(reader.read_u8()?))
     }
 }
+
+#[cfg(test)]
+mod test {
+    use std::panic::{self, AssertUnwindSafe};
+
+    use amplify::num::u5;
+
+    use super::dispatch::{decode_by_ranges, step_with, Dispatch};
+    use super::*;
+    use crate::data::Number;
+    use crate::isa::ExecStep;
+    use crate::library::{Cursor, LibSeg};
+    use crate::reg::{CoreRegs, NumericRegister, Reg32, RegA, RegF, RegR};
+
+    fn prefilled_regs() -> CoreRegs {
+        let mut regs = CoreRegs::default();
+        for idx in 0..32u8 {
+            let idx = Reg32::from(u5::with(idx));
+            for reg in RegA::ALL {
+                regs.set_n(reg, idx, Number::from_slice(vec![0x03; reg.bytes() as usize]));
+            }
+            for reg in RegF::ALL {
+                let num = Number::with(vec![0x3C; reg.bytes() as usize], reg.layout());
+                regs.set_n(reg, idx, num.as_ref());
+            }
+            for reg in RegR::ALL {
+                regs.set_n(reg, idx, Number::from_slice(vec![0x05; reg.bytes() as usize]));
+            }
+        }
+        for idx in 0..16u8 {
+            regs.set_s(idx, Some(ByteStr::with([0x5Au8; 64])));
+        }
+        regs
+    }
+
+    /// Runs execution step, returning `Err` if the instruction has panicked (some of the
+    /// instructions are not implemented for all of the register types).
+    fn step(
+        f: impl FnOnce(&mut CoreRegs) -> Option<ExecStep>,
+        regs: &mut CoreRegs,
+    ) -> Result<Option<ExecStep>, ()> {
+        panic::catch_unwind(AssertUnwindSafe(|| f(regs))).map_err(|_| ())
+    }
+
+    #[test]
+    fn dispatch_table_matches_ranges() {
+        let libs = LibSeg::default();
+        let data = [0x11u8; 64];
+        let site = LibSite::default();
+        let patterns = [[0x00u8; 16], [0xFFu8; 16], [0x5Au8; 16], [
+            0x01, 0x23, 0x45, 0x67, 0x89, 0xAB, 0xCD, 0xEF, 0xFE, 0xDC, 0xBA, 0x98, 0x76, 0x54,
+            0x32, 0x10,
+        ]];
+        let prefilled = prefilled_regs();
+
+        for opcode in 0..=u8::MAX {
+            for args in &patterns {
+                let mut code = vec![opcode];
+                code.extend_from_slice(args);
+
+                let mut table = Cursor::with(&code[..], &data[..], &libs);
+                let mut ranges = Cursor::with(&code[..], &data[..], &libs);
+                let expected = decode_by_ranges::<ReservedOp, _>(&mut ranges);
+                let instr = Dispatch::<ReservedOp, _>::DECODE[opcode as usize](&mut table);
+                assert_eq!(instr, expected, "opcode {opcode:#04X} args {args:02X?}");
+                assert_eq!(table.offset(), ranges.offset(), "opcode {opcode:#04X}");
+                // Instructions referencing libs segment can't be decoded from the random bytes
+                let instr = match expected {
+                    Ok(instr) => instr,
+                    Err(_) => continue,
+                };
+
+                for init in [CoreRegs::default(), prefilled.clone()] {
+                    let mut table_regs = init.clone();
+                    let mut table = Cursor::with(&code[..], &data[..], &libs);
+                    let next = step(
+                        |regs| {
+                            Dispatch::<ReservedOp, _>::STEP[opcode as usize](
+                                &mut table,
+                                regs,
+                                site,
+                                &(),
+                            )
+                        },
+                        &mut table_regs,
+                    );
+                    let mut match_regs = init;
+                    let expected =
+                        step(|regs| step_with(instr.clone(), regs, site, &()), &mut match_regs);
+                    assert_eq!(next, expected, "opcode {opcode:#04X} `{instr}`");
+                    assert_eq!(table_regs.st0, match_regs.st0, "opcode {opcode:#04X} `{instr}`");
+                    for reg in instr.dst_regs() {
+                        assert_eq!(
+                            table_regs.get(reg),
+                            match_regs.get(reg),
+                            "opcode {opcode:#04X} `{instr}`"
+                        );
+                    }
+                    if next.is_ok() {
+                        assert_eq!(table.offset(), ranges.offset(), "opcode {opcode:#04X}");
+                    }
+                }
+            }
+        }
+    }
+}
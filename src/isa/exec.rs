@@ -29,18 +29,27 @@ use alloc::string::String;
 #[cfg(all(feature = "alloc", not(feature = "std")))]
 use alloc::vec::Vec;
 use core::cmp::Ordering;
+use core::convert::TryFrom;
 use core::ops::{BitAnd, BitOr, BitXor, Neg, Rem, Shl, Shr};
 
+use amplify::num::apfloat::{ieee, Float};
+use amplify::ByteArray;
+use half::bf16;
 use sha2::Digest;
 
+use super::bytecode::dispatch::step_with;
+#[cfg(not(feature = "match-dispatch"))]
+use super::bytecode::dispatch::Dispatch;
 use super::{
-    ArithmeticOp, BitwiseOp, Bytecode, BytesOp, CmpOp, ControlFlowOp, Curve25519Op, DigestOp,
-    Instr, MoveOp, PutOp, ReservedOp, Secp256k1Op,
+    ArithmeticOp, BitwiseOp, Bytecode, BytesOp, CmpOp, ConstOp, ControlFlowOp, Curve25519Op,
+    DigestOp, Instr, IntrospectOp, MoveOp, PutOp, ReservedOp, Secp256k1Op,
 };
 use crate::data::{ByteStr, MaybeNumber, Number, NumberLayout};
 use crate::isa::{ExtendFlag, FloatEqFlag, IntFlags, MergeFlag, NoneEqFlag, SignFlag};
-use crate::library::{constants, IsaName, IsaSeg, LibSite};
-use crate::reg::{CoreRegs, NumericRegister, Reg, Reg32, RegA, RegA2, RegAR, RegBlockAR, RegR};
+use crate::library::{constants, IsaName, IsaSeg, LibSite, Read};
+use crate::reg::{
+    CoreRegs, NumericRegister, Reg, Reg32, RegA, RegA2, RegAR, RegBlockAR, RegF, RegR,
+};
 
 /// Turing machine movement after instruction execution
 #[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
@@ -95,6 +104,14 @@ pub trait InstructionSet: Bytecode + core::fmt::Display + core::fmt::Debug {
     /// List of registers which value is taken into the account by the instruction.
     fn src_regs(&self) -> BTreeSet<Reg>;
 
+    /// List of source registers which must be initialized for the instruction execution to be
+    /// meaningful. Excludes registers which uninitialized state is explicitly handled by the
+    /// instruction (like in `ifn` or `eq`) or which are just moved around (like in `mov` or `swp`).
+    ///
+    /// Used by [`CoreRegs::check_src_regs`]; defaults to [`InstructionSet::src_regs`].
+    #[inline]
+    fn required_src_regs(&self) -> BTreeSet<Reg> { self.src_regs() }
+
     /// List of registers which value may be changed by the instruction.
     fn dst_regs(&self) -> BTreeSet<Reg>;
 
@@ -113,6 +130,28 @@ pub trait InstructionSet: Bytecode + core::fmt::Display + core::fmt::Debug {
     /// Returns whether further execution should be stopped.
     // TODO: Take the instruction by reference
     fn exec(&self, regs: &mut CoreRegs, site: LibSite, context: &Self::Context<'_>) -> ExecStep;
+
+    /// Performs single step of the program execution: decodes the next instruction from the
+    /// `reader`, checks its source registers, executes it and accounts its complexity.
+    ///
+    /// # Returns
+    ///
+    /// Execution step, or `None` if the program execution must be halted: either the bytecode
+    /// has ended, or the instruction has accessed uninitialized registers, or the complexity
+    /// limit has been reached.
+    #[inline]
+    fn step<R>(
+        reader: &mut R,
+        regs: &mut CoreRegs,
+        site: LibSite,
+        context: &Self::Context<'_>,
+    ) -> Option<ExecStep>
+    where
+        Self: Sized,
+        R: Read,
+    {
+        step_with(Self::decode(reader).ok()?, regs, site, context)
+    }
 }
 
 impl<Extension> InstructionSet for Instr<Extension>
@@ -140,6 +179,8 @@ where
             Instr::Arithmetic(instr) => instr.src_regs(),
             Instr::Bitwise(instr) => instr.src_regs(),
             Instr::Bytes(instr) => instr.src_regs(),
+            Instr::Const(instr) => instr.src_regs(),
+            Instr::Introspect(instr) => instr.src_regs(),
             Instr::Digest(instr) => instr.src_regs(),
             #[cfg(feature = "secp256k1")]
             Instr::Secp256k1(instr) => instr.src_regs(),
@@ -151,6 +192,28 @@ where
         }
     }
 
+    fn required_src_regs(&self) -> BTreeSet<Reg> {
+        match self {
+            Instr::ControlFlow(instr) => instr.required_src_regs(),
+            Instr::Put(instr) => instr.required_src_regs(),
+            Instr::Move(instr) => instr.required_src_regs(),
+            Instr::Cmp(instr) => instr.required_src_regs(),
+            Instr::Arithmetic(instr) => instr.required_src_regs(),
+            Instr::Bitwise(instr) => instr.required_src_regs(),
+            Instr::Bytes(instr) => instr.required_src_regs(),
+            Instr::Const(instr) => instr.required_src_regs(),
+            Instr::Introspect(instr) => instr.required_src_regs(),
+            Instr::Digest(instr) => instr.required_src_regs(),
+            #[cfg(feature = "secp256k1")]
+            Instr::Secp256k1(instr) => instr.required_src_regs(),
+            #[cfg(feature = "curve25519")]
+            Instr::Curve25519(instr) => instr.required_src_regs(),
+            Instr::ExtensionCodes(instr) => instr.required_src_regs(),
+            Instr::ReservedInstruction(instr) => instr.required_src_regs(),
+            Instr::Nop => bset![],
+        }
+    }
+
     fn dst_regs(&self) -> BTreeSet<Reg> {
         match self {
             Instr::ControlFlow(instr) => instr.dst_regs(),
@@ -160,6 +223,8 @@ where
             Instr::Arithmetic(instr) => instr.dst_regs(),
             Instr::Bitwise(instr) => instr.dst_regs(),
             Instr::Bytes(instr) => instr.dst_regs(),
+            Instr::Const(instr) => instr.dst_regs(),
+            Instr::Introspect(instr) => instr.dst_regs(),
             Instr::Digest(instr) => instr.dst_regs(),
             #[cfg(feature = "secp256k1")]
             Instr::Secp256k1(instr) => instr.dst_regs(),
@@ -180,6 +245,8 @@ where
             Instr::Arithmetic(instr) => instr.complexity(),
             Instr::Bitwise(instr) => instr.complexity(),
             Instr::Bytes(instr) => instr.complexity(),
+            Instr::Const(instr) => instr.complexity(),
+            Instr::Introspect(instr) => instr.complexity(),
             Instr::Digest(instr) => instr.complexity(),
             #[cfg(feature = "secp256k1")]
             Instr::Secp256k1(instr) => instr.complexity(),
@@ -201,6 +268,8 @@ where
             Instr::Arithmetic(instr) => instr.exec(regs, site, &()),
             Instr::Bitwise(instr) => instr.exec(regs, site, &()),
             Instr::Bytes(instr) => instr.exec(regs, site, &()),
+            Instr::Const(instr) => instr.exec(regs, site, &()),
+            Instr::Introspect(instr) => instr.exec(regs, site, &()),
             Instr::Digest(instr) => instr.exec(regs, site, &()),
             #[cfg(feature = "secp256k1")]
             Instr::Secp256k1(instr) => instr.exec(regs, site, &()),
@@ -211,6 +280,22 @@ where
             Instr::Nop => ExecStep::Next,
         }
     }
+
+    #[cfg(not(feature = "match-dispatch"))]
+    #[inline]
+    fn step<R>(
+        reader: &mut R,
+        regs: &mut CoreRegs,
+        site: LibSite,
+        context: &Self::Context<'_>,
+    ) -> Option<ExecStep>
+    where
+        R: Read,
+    {
+        let opcode = reader.peek_u8().ok()?;
+        let table = &Dispatch::<Extension, R>::STEP;
+        table[opcode as usize](reader, regs, site, context)
+    }
 }
 
 impl InstructionSet for ControlFlowOp {
@@ -383,6 +468,20 @@ impl InstructionSet for MoveOp {
         }
     }
 
+    fn required_src_regs(&self) -> BTreeSet<Reg> {
+        match self {
+            MoveOp::MovA(..)
+            | MoveOp::DupA(..)
+            | MoveOp::SwpA(..)
+            | MoveOp::MovF(..)
+            | MoveOp::DupF(..)
+            | MoveOp::SwpF(..)
+            | MoveOp::MovR(..)
+            | MoveOp::DupR(..) => bset![],
+            _ => self.src_regs(),
+        }
+    }
+
     fn dst_regs(&self) -> BTreeSet<Reg> {
         match self {
             MoveOp::MovA(reg, idx1, idx2) => {
@@ -561,6 +660,19 @@ impl InstructionSet for CmpOp {
         }
     }
 
+    fn required_src_regs(&self) -> BTreeSet<Reg> {
+        match self {
+            CmpOp::EqA(..)
+            | CmpOp::EqF(..)
+            | CmpOp::EqR(..)
+            | CmpOp::IfZA(..)
+            | CmpOp::IfZR(..)
+            | CmpOp::IfNA(..)
+            | CmpOp::IfNR(..) => bset![],
+            _ => self.src_regs(),
+        }
+    }
+
     fn dst_regs(&self) -> BTreeSet<Reg> {
         match self {
             CmpOp::St(_, reg, idx) => {
@@ -809,17 +921,13 @@ impl InstructionSet for ArithmeticOp {
                 regs.get_n(reg, idx).and_then(|val| {
                     if step.as_i8() < 0 {
                         let mut n = Number::from(-step.as_i8());
-                        debug_assert!(
-                            n.reshape(val.layout()),
-                            "reshape target byte length is always greater"
-                        );
+                        let reshaped = n.reshape(val.layout());
+                        debug_assert!(reshaped, "reshape target byte length is always greater");
                         val.int_sub(n, IntFlags { signed: false, wrap: false })
                     } else {
                         let mut n = Number::from(*step);
-                        debug_assert!(
-                            n.reshape(val.layout()),
-                            "reshape target byte length is always greater"
-                        );
+                        let reshaped = n.reshape(val.layout());
+                        debug_assert!(reshaped, "reshape target byte length is always greater");
                         val.int_add(n, IntFlags { signed: false, wrap: false })
                     }
                 }),
@@ -1124,6 +1232,13 @@ impl InstructionSet for BytesOp {
         }
     }
 
+    fn required_src_regs(&self) -> BTreeSet<Reg> {
+        match self {
+            BytesOp::Mov(..) | BytesOp::Swp(..) | BytesOp::Eq(..) => bset![],
+            _ => self.src_regs(),
+        }
+    }
+
     fn dst_regs(&self) -> BTreeSet<Reg> {
         match self {
             BytesOp::Put(reg, _, _) => {
@@ -1385,6 +1500,109 @@ impl InstructionSet for BytesOp {
     }
 }
 
+impl InstructionSet for ConstOp {
+    type Context<'ctx> = ();
+
+    #[inline]
+    fn isa_ids() -> IsaSeg { IsaSeg::default() }
+
+    fn src_regs(&self) -> BTreeSet<Reg> { bset![] }
+
+    fn dst_regs(&self) -> BTreeSet<Reg> {
+        match self {
+            ConstOp::ZeroA(reg, idx) | ConstOp::OneA(reg, idx) | ConstOp::MaxA(reg, idx) => {
+                bset![Reg::A(*reg, *idx)]
+            }
+            ConstOp::ZeroF(reg, idx) | ConstOp::OneF(reg, idx) => bset![Reg::F(*reg, *idx)],
+            ConstOp::ZeroR(reg, idx) | ConstOp::OneR(reg, idx) | ConstOp::MaxR(reg, idx) => {
+                bset![Reg::R(*reg, *idx)]
+            }
+        }
+    }
+
+    #[inline]
+    fn complexity(&self) -> u64 { 1 }
+
+    fn exec(&self, regs: &mut CoreRegs, _: LibSite, _: &()) -> ExecStep {
+        match self {
+            ConstOp::ZeroA(reg, index) => {
+                regs.set_n(reg, index, MaybeNumber::zero(reg.layout()));
+            }
+            ConstOp::ZeroF(reg, index) => {
+                regs.set_n(reg, index, MaybeNumber::zero(reg.layout()));
+            }
+            ConstOp::ZeroR(reg, index) => {
+                regs.set_n(reg, index, MaybeNumber::zero(reg.layout()));
+            }
+            ConstOp::OneA(reg, index) => {
+                regs.set_n(reg, index, MaybeNumber::one(reg.layout()));
+            }
+            ConstOp::OneF(reg, index) => {
+                let one = match reg {
+                    RegF::F16B => MaybeNumber::from(bf16::ONE),
+                    RegF::F16 => ieee::Half::from_u256(1u64.into()).value.into(),
+                    RegF::F32 => ieee::Single::from_u256(1u64.into()).value.into(),
+                    RegF::F64 => ieee::Double::from_u256(1u64.into()).value.into(),
+                    RegF::F80 => ieee::X87DoubleExtended::from_u256(1u64.into()).value.into(),
+                    RegF::F128 => ieee::Quad::from_u256(1u64.into()).value.into(),
+                    RegF::F256 => ieee::Oct::from_u256(1u64.into()).value.into(),
+                    RegF::F512 => MaybeNumber::none(),
+                };
+                if !regs.set_n(reg, index, one) {
+                    regs.st0 = false;
+                }
+            }
+            ConstOp::OneR(reg, index) => {
+                regs.set_n(reg, index, MaybeNumber::one(reg.layout()));
+            }
+            ConstOp::MaxA(reg, index) => {
+                regs.set_n(reg, index, !Number::zero(reg.layout()));
+            }
+            ConstOp::MaxR(reg, index) => {
+                regs.set_n(reg, index, !Number::zero(reg.layout()));
+            }
+        }
+        ExecStep::Next
+    }
+}
+
+impl InstructionSet for IntrospectOp {
+    type Context<'ctx> = ();
+
+    #[inline]
+    fn isa_ids() -> IsaSeg { IsaSeg::default() }
+
+    fn src_regs(&self) -> BTreeSet<Reg> { bset![] }
+
+    fn dst_regs(&self) -> BTreeSet<Reg> {
+        match self {
+            IntrospectOp::Site(id, pos) => {
+                bset![Reg::new(RegR::R256, *id), Reg::new(RegA::A16, *pos)]
+            }
+            IntrospectOp::Icnt(dst) => bset![Reg::new(RegA::A32, *dst)],
+        }
+    }
+
+    #[inline]
+    fn complexity(&self) -> u64 { 1 }
+
+    fn exec(&self, regs: &mut CoreRegs, site: LibSite, _: &()) -> ExecStep {
+        match self {
+            IntrospectOp::Site(id, pos) => {
+                regs.set_n(RegR::R256, id, site.lib.to_byte_array());
+                regs.set_n(RegA::A16, pos, site.pos);
+            }
+            IntrospectOp::Icnt(dst) => {
+                let count = u32::try_from(regs.instr_count()).ok();
+                if !regs.set_n(RegA::A32, dst, count) {
+                    regs.st0 = false;
+                }
+            }
+        }
+        ExecStep::Next
+    }
+}
+
 impl InstructionSet for DigestOp {
     type Context<'ctx> = ();
 
@@ -1677,9 +1895,104 @@ impl InstructionSet for ReservedOp {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::data::Step;
+    use crate::reg::Reg16;
     #[cfg(feature = "secp256k1")]
     use crate::reg::{Reg8, RegBlockAR};
 
+    #[test]
+    fn site_test() {
+        let mut register = CoreRegs::default();
+        let lib_site = LibSite::with(0x1234, [0xA5; 32].into());
+        IntrospectOp::Site(Reg16::Reg1, Reg16::Reg2).exec(&mut register, lib_site, &());
+        assert_eq!(register.get_n(RegR::R256, Reg32::Reg1).unwrap(), Number::from([0xA5u8; 32]));
+        assert_eq!(register.get_n(RegA::A16, Reg32::Reg2).unwrap(), Number::from(0x1234u16));
+        assert!(register.st0);
+    }
+
+    #[test]
+    fn icnt_test() {
+        let mut register = CoreRegs::default();
+        let lib_site = LibSite::default();
+        IntrospectOp::Icnt(Reg32::Reg0).exec(&mut register, lib_site, &());
+        assert_eq!(register.get_n(RegA::A32, Reg32::Reg0).unwrap(), Number::from(0u32));
+        register.acc_complexity(Instr::<ReservedOp>::Nop);
+        register.acc_complexity(Instr::<ReservedOp>::Nop);
+        IntrospectOp::Icnt(Reg32::Reg0).exec(&mut register, lib_site, &());
+        assert_eq!(register.get_n(RegA::A32, Reg32::Reg0).unwrap(), Number::from(2u32));
+        assert!(register.st0);
+    }
+
+    #[test]
+    fn stp_test() {
+        // The step is reshaped to the register layout by the instruction itself, not by a debug
+        // assertion, so this must also pass with `cargo test --release`
+        let mut register = CoreRegs::default();
+        let lib_site = LibSite::default();
+        for reg in RegA::ALL {
+            let num = |val: u8| Number::from(val).reshaped(reg.layout(), false).unwrap();
+            register.set_n(reg, Reg32::Reg4, num(10));
+            ArithmeticOp::Stp(reg, Reg32::Reg4, Step::with(3)).exec(&mut register, lib_site, &());
+            assert_eq!(register.get_n(reg, Reg32::Reg4).unwrap(), num(13), "{}", reg);
+            ArithmeticOp::Stp(reg, Reg32::Reg4, Step::with(-5)).exec(&mut register, lib_site, &());
+            assert_eq!(register.get_n(reg, Reg32::Reg4).unwrap(), num(8), "{}", reg);
+            assert!(register.st0);
+        }
+    }
+
+    #[test]
+    fn const_instructions() {
+        const A64: RegA = match RegA::with(64) {
+            Some(reg) => reg,
+            None => panic!("no a64 register"),
+        };
+        const CODE: [Instr; 3] = [
+            Instr::Const(ConstOp::OneA(A64, Reg32::Reg0)),
+            Instr::Arithmetic(ArithmeticOp::AddA(
+                IntFlags::unsigned_checked(),
+                A64,
+                Reg32::Reg0,
+                Reg32::Reg1,
+            )),
+            Instr::ControlFlow(ControlFlowOp::Ret),
+        ];
+
+        let mut register = CoreRegs::default();
+        register.set_n(A64, Reg32::Reg1, 1u64);
+        for instr in &CODE {
+            instr.exec(&mut register, LibSite::default(), &());
+        }
+        assert_eq!(register.get_n(A64, Reg32::Reg1).unwrap(), Number::from(2u64));
+    }
+
+    #[test]
+    fn const_load_test() {
+        let mut register = CoreRegs::default();
+        let lib_site = LibSite::default();
+
+        ConstOp::ZeroA(RegA::A64, Reg32::Reg1).exec(&mut register, lib_site, &());
+        assert_eq!(register.get_n(RegA::A64, Reg32::Reg1).unwrap(), Number::from(0u64));
+        ConstOp::OneA(RegA::A64, Reg32::Reg1).exec(&mut register, lib_site, &());
+        assert_eq!(register.get_n(RegA::A64, Reg32::Reg1).unwrap(), Number::from(1u64));
+        ConstOp::MaxA(RegA::A64, Reg32::Reg1).exec(&mut register, lib_site, &());
+        assert_eq!(register.get_n(RegA::A64, Reg32::Reg1).unwrap(), Number::from(u64::MAX));
+
+        ConstOp::OneR(RegR::R128, Reg32::Reg2).exec(&mut register, lib_site, &());
+        assert_eq!(register.get_n(RegR::R128, Reg32::Reg2).unwrap(), Number::from(1u128));
+        ConstOp::MaxR(RegR::R128, Reg32::Reg2).exec(&mut register, lib_site, &());
+        assert_eq!(register.get_n(RegR::R128, Reg32::Reg2).unwrap(), Number::from(u128::MAX));
+
+        ConstOp::OneF(RegF::F64, Reg32::Reg3).exec(&mut register, lib_site, &());
+        assert_eq!(
+            register.get_n(RegF::F64, Reg32::Reg3),
+            MaybeNumber::from(ieee::Double::from_u256(1u64.into()).value)
+        );
+        assert!(register.st0);
+        ConstOp::OneF(RegF::F512, Reg32::Reg3).exec(&mut register, lib_site, &());
+        assert_eq!(register.get_n(RegF::F512, Reg32::Reg3), MaybeNumber::none());
+        assert!(!register.st0);
+    }
+
     #[test]
     fn bytes_con_test() {
         let mut register = CoreRegs::default();
@@ -447,9 +447,12 @@ impl FromStr for IntFlags {
 
 impl IntFlags {
     /// Constructs integer arithmetic flags from `u2` value (used in bytecode serialization)
+    ///
+    /// NB: decoders up to v0.11.0-beta.6 took the `wrap` flag from the same bit as `signed`, so
+    /// bytecode with `sc` and `uw` flags was executed as `sw` and `uc` correspondingly.
     pub fn from_u2(val: u2) -> Self {
         let val = val.to_u8();
-        IntFlags { signed: val & 0x01 == 1, wrap: val & 0x02 >> 1 == 1 }
+        IntFlags { signed: val & 0x01 == 1, wrap: (val & 0x02) >> 1 == 1 }
     }
 
     /// Returns `u2` representation of integer arithmetic flags (used in bytecode serialization).
@@ -457,19 +460,19 @@ impl IntFlags {
 
     /// Constructs variant for unsigned checked operation flags
     #[inline]
-    pub fn unsigned_checked() -> Self { IntFlags { signed: false, wrap: false } }
+    pub const fn unsigned_checked() -> Self { IntFlags { signed: false, wrap: false } }
 
     /// Constructs variant for signed checked operation flags
     #[inline]
-    pub fn signed_checked() -> Self { IntFlags { signed: true, wrap: false } }
+    pub const fn signed_checked() -> Self { IntFlags { signed: true, wrap: false } }
 
     /// Constructs variant for unsigned wrapped operation flags
     #[inline]
-    pub fn unsigned_wrapped() -> Self { IntFlags { signed: false, wrap: true } }
+    pub const fn unsigned_wrapped() -> Self { IntFlags { signed: false, wrap: true } }
 
     /// Constructs variant for signed wrapped operation flags
     #[inline]
-    pub fn signed_wrapped() -> Self { IntFlags { signed: true, wrap: true } }
+    pub const fn signed_wrapped() -> Self { IntFlags { signed: true, wrap: true } }
 }
 
 impl From<u2> for IntFlags {
@@ -995,3 +998,38 @@ impl From<&DeleteFlag> for u2 {
 impl From<DeleteFlag> for u2 {
     fn from(flag: DeleteFlag) -> u2 { flag.as_u2() }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::isa::{ArithmeticOp, Instr, ReservedOp};
+    use crate::library::Lib;
+    use crate::reg::{Reg32, RegA};
+
+    #[test]
+    fn int_flags_encoding() {
+        let cases = [
+            (0b00, IntFlags::unsigned_checked(), "uc"),
+            (0b01, IntFlags::signed_checked(), "sc"),
+            (0b10, IntFlags::unsigned_wrapped(), "uw"),
+            (0b11, IntFlags::signed_wrapped(), "sw"),
+        ];
+        for (bits, flags, s) in cases {
+            assert_eq!(IntFlags::from_u2(u2::with(bits)), flags);
+            assert_eq!(flags.as_u2(), u2::with(bits));
+            assert_eq!(flags.to_string(), s);
+            assert_eq!(IntFlags::from_str(s), Ok(flags));
+
+            let instr = Instr::<ReservedOp>::Arithmetic(ArithmeticOp::AddA(
+                flags,
+                RegA::A64,
+                Reg32::Reg0,
+                Reg32::Reg1,
+            ));
+            let lib = Lib::assemble(core::slice::from_ref(&instr)).unwrap();
+            // Flags follow the single-bit family selector
+            assert_eq!((lib.code[1] >> 1) & 0b11, bits);
+            assert_eq!(lib.disassemble::<Instr<ReservedOp>>().unwrap(), vec![instr]);
+        }
+    }
+}
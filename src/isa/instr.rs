@@ -74,18 +74,27 @@ where
     // 0b00_110_***
     Bytes(BytesOp),
 
-    /// Cryptographic hashing functions. See [`DigestOp`] for the details.
+    /// Instructions loading constant values into registers. See [`ConstOp`] for the details.
     // 0b01_000_***
+    Const(ConstOp),
+
+    /// Instructions introspecting the state of the running program. See [`IntrospectOp`] for the
+    /// details.
+    // 0b01_001_***
+    Introspect(IntrospectOp),
+
+    /// Cryptographic hashing functions. See [`DigestOp`] for the details.
+    // 0b10_000_***
     Digest(DigestOp),
 
     #[cfg(feature = "secp256k1")]
     /// Operations on Secp256k1 elliptic curve. See [`Secp256k1Op`] for the details.
-    // 0b01_001_0**
+    // 0b10_001_0**
     Secp256k1(Secp256k1Op),
 
     #[cfg(feature = "curve25519")]
     /// Operations on Curve25519 elliptic curve. See [`Curve25519Op`] for the details.
-    // 0b01_001_1**
+    // 0b10_001_1**
     Curve25519(Curve25519Op),
 
     /// Extension operations which can be provided by a host environment provided via generic
@@ -851,6 +860,73 @@ pub enum BytesOp {
     Rev(/** Source */ RegS, /** Destination */ RegS),
 }
 
+/// Instructions loading frequently used constant values into registers.
+///
+/// Unlike [`PutOp`] these instructions do not reference the data segment and are encoded with a
+/// single argument byte. They never modify the value of `st0`, except for [`ConstOp::OneF`] on
+/// `f512` registers.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Display)]
+pub enum ConstOp {
+    /// Sets a value of `A` register to zero
+    #[display("zero    {0}{1}")]
+    ZeroA(RegA, Reg32),
+
+    /// Sets a value of `F` register to positive zero
+    #[display("zero    {0}{1}")]
+    ZeroF(RegF, Reg32),
+
+    /// Sets a value of `R` register to zero
+    #[display("zero    {0}{1}")]
+    ZeroR(RegR, Reg32),
+
+    /// Sets a value of `A` register to one
+    #[display("one     {0}{1}")]
+    OneA(RegA, Reg32),
+
+    /// Sets a value of `F` register to `1.0`.
+    ///
+    /// Since tapered floats are not supported yet, for `f512` registers the operation sets the
+    /// destination register into undefined state and `st0` to `false`.
+    #[display("one     {0}{1}")]
+    OneF(RegF, Reg32),
+
+    /// Sets a value of `R` register to one
+    #[display("one     {0}{1}")]
+    OneR(RegR, Reg32),
+
+    /// Sets all bits of `A` register to one, i.e. assigns it the maximal unsigned value
+    #[display("max     {0}{1}")]
+    MaxA(RegA, Reg32),
+
+    /// Sets all bits of `R` register to one
+    #[display("max     {0}{1}")]
+    MaxR(RegR, Reg32),
+}
+
+/// Instructions introspecting the state of the running program.
+///
+/// Allow scripts to implement self-referential commitments and re-entry checks without a support
+/// from the host environment.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Display)]
+pub enum IntrospectOp {
+    /// Loads the id of the currently executed library into `r256` register and the offset of the
+    /// current instruction within the library code segment into `a16` register. Does not modify
+    /// the value of `st0`.
+    #[display("site    r256{0},a16{1}")]
+    Site(
+        /** Index of `r256` register to save library id to */ Reg16,
+        /** Index of `a16` register to save instruction offset to */ Reg16,
+    ),
+
+    /// Loads the number of instructions executed so far (value of `ic0` register, not including
+    /// the current instruction) into `a32` register.
+    ///
+    /// If the value does not fit into 32 bits, sets the destination register into undefined state
+    /// and `st0` to `false`. Otherwise, `st0` value is not affected.
+    #[display("icnt    a32{0}")]
+    Icnt(/** Index of `a32` register to save the counter to */ Reg32),
+}
+
 /// Cryptographic hashing functions
 #[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Display)]
 #[non_exhaustive]
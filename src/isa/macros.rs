@@ -67,7 +67,8 @@ macro_rules! aluasm_isa {
         use ::std::boxed::Box;
 
         use ::aluvm::isa::{
-            ArithmeticOp, BitwiseOp, BytesOp, CmpOp, ControlFlowOp, DigestOp, ExtendFlag, FloatEqFlag, Instr, IntFlags,
+            ArithmeticOp, BitwiseOp, BytesOp, CmpOp, ConstOp, ControlFlowOp, DigestOp, ExtendFlag, FloatEqFlag, Instr, IntFlags,
+            IntrospectOp,
             MergeFlag, MoveOp, PutOp, RoundingFlag, Secp256k1Op, SignFlag, NoneEqFlag
         };
         use ::aluvm::reg::{
@@ -216,6 +217,33 @@ macro_rules! instr {
             $crate::_reg_idx!($idx),
         ))
     };
+    (zero $reg:ident[$idx:literal]) => {
+        Instr::Const($crate::_reg_sfx!(ConstOp, Zero, $reg)(
+            $crate::_reg_ty!(Reg, $reg),
+            $crate::_reg_idx!($idx),
+        ))
+    };
+    (one $reg:ident[$idx:literal]) => {
+        Instr::Const($crate::_reg_sfx!(ConstOp, One, $reg)(
+            $crate::_reg_ty!(Reg, $reg),
+            $crate::_reg_idx!($idx),
+        ))
+    };
+    (max $reg:ident[$idx:literal]) => {
+        Instr::Const($crate::_reg_sfx!(ConstOp, Max, $reg)(
+            $crate::_reg_ty!(Reg, $reg),
+            $crate::_reg_idx!($idx),
+        ))
+    };
+    (site r256[$id_idx:literal], a16[$pos_idx:literal]) => {
+        Instr::Introspect(IntrospectOp::Site(
+            $crate::_reg_idx16!($id_idx),
+            $crate::_reg_idx16!($pos_idx),
+        ))
+    };
+    (icnt a32[$idx:literal]) => {
+        Instr::Introspect(IntrospectOp::Icnt($crate::_reg_idx!($idx)))
+    };
 
     (extr s16[$idx:literal], $reg:ident[$reg_idx:literal], a16[$offset_idx:literal]) => {
         Instr::Bytes(BytesOp::Extr(
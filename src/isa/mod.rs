@@ -30,6 +30,7 @@ mod exec;
 mod flags;
 mod instr;
 pub mod opcodes;
+mod opmap;
 
 pub use bytecode::{Bytecode, BytecodeError};
 pub use exec::{ExecStep, InstructionSet};
@@ -38,9 +39,10 @@ pub use flags::{
     ParseFlagError, RoundingFlag, SignFlag, SplitFlag,
 };
 pub use instr::{
-    ArithmeticOp, BitwiseOp, BytesOp, CmpOp, ControlFlowOp, Curve25519Op, DigestOp, Instr, MoveOp,
-    PutOp, ReservedOp, Secp256k1Op,
+    ArithmeticOp, BitwiseOp, BytesOp, CmpOp, ConstOp, ControlFlowOp, Curve25519Op, DigestOp, Instr,
+    IntrospectOp, MoveOp, PutOp, ReservedOp, Secp256k1Op,
 };
+pub use opmap::{OpFamily, OpcodeMap, OpcodeMapError};
 
 /// List of standardised ISA extensions.
 #[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, Display)]
@@ -103,11 +103,25 @@ pub const INSTR_INS: u8 = 0b00_111_101;
 pub const INSTR_DEL: u8 = 0b00_111_110;
 pub const INSTR_REV: u8 = 0b00_111_111;
 
+// Instructions loading constant values into registers
+pub const INSTR_ZEROA: u8 = 0b01_000_000;
+pub const INSTR_ZEROF: u8 = 0b01_000_001;
+pub const INSTR_ZEROR: u8 = 0b01_000_010;
+pub const INSTR_ONEA: u8 = 0b01_000_011;
+pub const INSTR_ONEF: u8 = 0b01_000_100;
+pub const INSTR_ONER: u8 = 0b01_000_101;
+pub const INSTR_MAXA: u8 = 0b01_000_110;
+pub const INSTR_MAXR: u8 = 0b01_000_111;
+
+// Instructions introspecting the state of the running program
+pub const INSTR_SITE: u8 = 0b01_001_000;
+pub const INSTR_ICNT: u8 = 0b01_001_001;
+
 // No-operation instruction
 pub const INSTR_NOP: u8 = 0b11_111_111;
 
 // Reserved operations which can be used by future AluVM versions
-pub const INSTR_RESV_FROM: u8 = 0b01_000_000;
+pub const INSTR_RESV_FROM: u8 = 0b01_001_010;
 pub const INSTR_RESV_TO: u8 = 0b01_111_111;
 
 // ## ISA extensions:
@@ -0,0 +1,318 @@
+// Reference rust implementation of AluVM (arithmetic logic unit virtual machine).
+// To find more on AluVM please check <https://aluvm.org>
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2021-2024 by
+//     Dr Maxim Orlovsky <orlovsky@ubideco.org>
+//
+// Copyright (C) 2021-2022 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2023-2024 UBIDECO Institute. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Map of the primary (8-bit) opcode space to the instruction families.
+
+use core::ops::RangeInclusive;
+
+use super::opcodes::*;
+use super::{
+    ArithmeticOp, BitwiseOp, Bytecode, BytesOp, CmpOp, ConstOp, ControlFlowOp, Curve25519Op,
+    DigestOp, Instr, InstructionSet, IntrospectOp, MoveOp, PutOp, Secp256k1Op,
+};
+
+/// Instruction family to which an opcode is assigned.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Display)]
+#[display(Debug)]
+pub enum OpFamily {
+    /// Control-flow instructions ([`ControlFlowOp`])
+    ControlFlow,
+    /// Instructions setting register values ([`PutOp`])
+    Put,
+    /// Instructions moving and swapping register values ([`MoveOp`])
+    Move,
+    /// Instructions comparing register values ([`CmpOp`])
+    Cmp,
+    /// Arithmetic instructions ([`ArithmeticOp`])
+    Arithmetic,
+    /// Bit operations & boolean algebra instructions ([`BitwiseOp`])
+    Bitwise,
+    /// Operations on byte strings ([`BytesOp`])
+    Bytes,
+    /// Instructions loading constant values into registers ([`ConstOp`])
+    Const,
+    /// Instructions introspecting the state of the running program ([`IntrospectOp`])
+    Introspect,
+    /// Opcodes reserved for the future versions of the core ISA
+    Reserved,
+    /// No-operation instruction
+    Nop,
+    /// Cryptographic hashing functions ([`DigestOp`])
+    Digest,
+    /// Operations on Secp256k1 curve ([`Secp256k1Op`])
+    Secp256k1,
+    /// Operations on Curve25519 ([`Curve25519Op`])
+    Curve25519,
+    /// Instructions provided by an ISA extension external to this crate
+    Extension,
+}
+
+impl OpFamily {
+    /// Detects whether the family belongs to the ISA extension opcode space (from
+    /// [`INSTR_ISAE_FROM`] to [`INSTR_ISAE_TO`]).
+    pub const fn is_extension(self) -> bool {
+        matches!(
+            self,
+            OpFamily::Digest | OpFamily::Secp256k1 | OpFamily::Curve25519 | OpFamily::Extension
+        )
+    }
+
+    /// Returns family of a given instruction
+    pub fn of<Extension>(instr: &Instr<Extension>) -> OpFamily
+    where
+        Extension: InstructionSet,
+    {
+        match instr {
+            Instr::ControlFlow(_) => OpFamily::ControlFlow,
+            Instr::Put(_) => OpFamily::Put,
+            Instr::Move(_) => OpFamily::Move,
+            Instr::Cmp(_) => OpFamily::Cmp,
+            Instr::Arithmetic(_) => OpFamily::Arithmetic,
+            Instr::Bitwise(_) => OpFamily::Bitwise,
+            Instr::Bytes(_) => OpFamily::Bytes,
+            Instr::Const(_) => OpFamily::Const,
+            Instr::Introspect(_) => OpFamily::Introspect,
+            Instr::Digest(_) => OpFamily::Digest,
+            #[cfg(feature = "secp256k1")]
+            Instr::Secp256k1(_) => OpFamily::Secp256k1,
+            #[cfg(feature = "curve25519")]
+            Instr::Curve25519(_) => OpFamily::Curve25519,
+            Instr::ExtensionCodes(_) => OpFamily::Extension,
+            Instr::ReservedInstruction(_) => OpFamily::Reserved,
+            Instr::Nop => OpFamily::Nop,
+        }
+    }
+}
+
+/// Errors detected while building [`OpcodeMap`]
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug, Display)]
+#[display(doc_comments)]
+pub enum OpcodeMapError {
+    /// opcode {opcode:#04X} is assigned to both {existing} and {new} instruction families.
+    Collision {
+        /// Opcode assigned to both families
+        opcode: u8,
+        /// Family to which the opcode was assigned first
+        existing: OpFamily,
+        /// Family which attempted to re-assign the opcode
+        new: OpFamily,
+    },
+
+    /// opcode {opcode:#04X} of {family} instruction family lies outside of the opcode space
+    /// allocated for its kind of instructions.
+    OutOfSpace {
+        /// Opcode outside of the allowed space
+        opcode: u8,
+        /// Family to which the opcode was assigned
+        family: OpFamily,
+    },
+
+    /// opcode {0:#04X} is not assigned to any of the core instruction families.
+    Unassigned(u8),
+}
+
+#[cfg(feature = "std")]
+impl ::std::error::Error for OpcodeMapError {}
+
+/// Map of the whole primary (8-bit) opcode space, recording to which instruction family each of
+/// the opcodes is assigned.
+///
+/// The map is constructed from [`Bytecode::instr_range`] of the instruction families, and fails
+/// on any overlap between them. Since bytecode decoding dispatches on these ranges, an overlap
+/// would make some instructions silently decode as a different instruction family.
+///
+/// The map reflects the opcode numbering as it is used by the existing bytecode. It does not
+/// cover the opcodes which some instructions encode to outside of their family range: `blake3`
+/// is encoded as [`INSTR_BLAKE3`], which lies in the ISA extension space, and `nop` is encoded
+/// as [`INSTR_TEST`], while [`INSTR_NOP`] is the opcode decoded as `nop`.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct OpcodeMap([Option<OpFamily>; 256]);
+
+impl Default for OpcodeMap {
+    fn default() -> Self { OpcodeMap([None; 256]) }
+}
+
+impl OpcodeMap {
+    /// Constructs map of all instruction families defined by this crate (including the ISA
+    /// extensions which may be disabled by crate features) and checks it for consistency.
+    ///
+    /// # Errors
+    ///
+    /// If any two families overlap, if a core family falls outside of the core opcode space or
+    /// an extension family outside of the ISA extension space, or if some of the core opcodes
+    /// are left unassigned.
+    pub fn with_std() -> Result<Self, OpcodeMapError> {
+        let mut map = OpcodeMap::default();
+        map.assign(OpFamily::ControlFlow, ControlFlowOp::instr_range())?;
+        map.assign(OpFamily::Put, PutOp::instr_range())?;
+        map.assign(OpFamily::Move, MoveOp::instr_range())?;
+        map.assign(OpFamily::Cmp, CmpOp::instr_range())?;
+        map.assign(OpFamily::Arithmetic, ArithmeticOp::instr_range())?;
+        map.assign(OpFamily::Bitwise, BitwiseOp::instr_range())?;
+        map.assign(OpFamily::Bytes, BytesOp::instr_range())?;
+        map.assign(OpFamily::Const, ConstOp::instr_range())?;
+        map.assign(OpFamily::Introspect, IntrospectOp::instr_range())?;
+        map.assign(OpFamily::Reserved, INSTR_RESV_FROM..=INSTR_RESV_TO)?;
+        map.assign(OpFamily::Nop, INSTR_NOP..=INSTR_NOP)?;
+        map.assign(OpFamily::Digest, DigestOp::instr_range())?;
+        map.assign(OpFamily::Secp256k1, Secp256k1Op::instr_range())?;
+        map.assign(OpFamily::Curve25519, Curve25519Op::instr_range())?;
+
+        if let Some(opcode) = (0..INSTR_ISAE_FROM).find(|opcode| map.family(*opcode).is_none()) {
+            return Err(OpcodeMapError::Unassigned(opcode));
+        }
+        Ok(map)
+    }
+
+    /// Assigns a range of opcodes to an instruction family. Can be used to check that the opcodes
+    /// of a third-party ISA extension do not collide with the ones defined by this crate.
+    ///
+    /// # Errors
+    ///
+    /// If any of the opcodes is already assigned to some other family, or if the range lies
+    /// outside of the opcode space allowed for the family.
+    pub fn assign(
+        &mut self,
+        family: OpFamily,
+        range: RangeInclusive<u8>,
+    ) -> Result<(), OpcodeMapError> {
+        let space = match family {
+            OpFamily::Nop => INSTR_NOP..=INSTR_NOP,
+            family if family.is_extension() => INSTR_ISAE_FROM..=INSTR_ISAE_TO,
+            _ => 0..=INSTR_RESV_TO,
+        };
+        for opcode in range.clone() {
+            if !space.contains(&opcode) {
+                return Err(OpcodeMapError::OutOfSpace { opcode, family });
+            }
+            if let Some(existing) = self.0[opcode as usize] {
+                return Err(OpcodeMapError::Collision { opcode, existing, new: family });
+            }
+        }
+        for opcode in range {
+            self.0[opcode as usize] = Some(family);
+        }
+        Ok(())
+    }
+
+    /// Returns family to which the opcode is assigned, if any.
+    #[inline]
+    pub fn family(&self, opcode: u8) -> Option<OpFamily> { self.0[opcode as usize] }
+
+    /// Returns range of opcodes assigned to a given family, or `None` if the family has no
+    /// assigned opcodes or its opcodes do not form a single contiguous range.
+    pub fn range(&self, family: OpFamily) -> Option<RangeInclusive<u8>> {
+        let start = self.opcodes(family).next()?;
+        let end = self.opcodes(family).last()?;
+        if (start..=end).all(|opcode| self.family(opcode) == Some(family)) {
+            Some(start..=end)
+        } else {
+            None
+        }
+    }
+
+    /// Iterates over opcodes assigned to a given family.
+    pub fn opcodes(&self, family: OpFamily) -> impl Iterator<Item = u8> + '_ {
+        (0..=u8::MAX).filter(move |opcode| self.family(*opcode) == Some(family))
+    }
+
+    /// Iterates over opcodes which are not assigned to any of the instruction families.
+    pub fn unassigned(&self) -> impl Iterator<Item = u8> + '_ {
+        (0..=u8::MAX).filter(move |opcode| self.family(*opcode).is_none())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::isa::ReservedOp;
+    use crate::library::{Cursor, LibSeg};
+    use crate::reg::{Reg16, RegS};
+
+    #[test]
+    fn opcode_map() {
+        let map = OpcodeMap::with_std().unwrap();
+        assert_eq!(map.range(OpFamily::ControlFlow), Some(INSTR_FAIL..=INSTR_RET));
+        assert_eq!(map.range(OpFamily::Reserved), Some(INSTR_RESV_FROM..=INSTR_RESV_TO));
+        assert_eq!(map.family(INSTR_NOP), Some(OpFamily::Nop));
+        assert_eq!(map.range(OpFamily::Digest), Some(INSTR_RIPEMD..=INSTR_SHA512));
+        assert_eq!(map.family(INSTR_BLAKE3), None);
+        assert!(map.unassigned().all(|opcode| (INSTR_ISAE_FROM..=INSTR_ISAE_TO).contains(&opcode)));
+    }
+
+    #[test]
+    fn opcode_collision() {
+        let mut map = OpcodeMap::with_std().unwrap();
+        assert_eq!(
+            map.assign(OpFamily::Extension, INSTR_ED_NEG..=INSTR_ED_NEG + 1),
+            Err(OpcodeMapError::Collision {
+                opcode: INSTR_ED_NEG,
+                existing: OpFamily::Curve25519,
+                new: OpFamily::Extension
+            })
+        );
+        assert_eq!(
+            map.assign(OpFamily::Extension, INSTR_RESV_TO..=INSTR_ISAE_FROM),
+            Err(OpcodeMapError::OutOfSpace { opcode: INSTR_RESV_TO, family: OpFamily::Extension })
+        );
+        map.assign(OpFamily::Extension, 0xC0..=0xCF).unwrap();
+        assert_eq!(map.range(OpFamily::Extension), Some(0xC0..=0xCF));
+    }
+
+    #[test]
+    fn opcode_map_matches_decoding() {
+        let map = OpcodeMap::with_std().unwrap();
+        let libs = LibSeg::default();
+        let core = (0..INSTR_ISAE_FROM).chain(DigestOp::instr_range());
+        for opcode in core {
+            let mut code = [0u8; 32];
+            code[0] = opcode;
+            let mut reader = Cursor::with(&code[..], &[] as &[u8], &libs);
+            // Some instructions can't be decoded without data or libs segment
+            let instr = match Instr::<ReservedOp>::decode(&mut reader) {
+                Ok(instr) => instr,
+                Err(_) => continue,
+            };
+            assert_eq!(Some(OpFamily::of(&instr)), map.family(opcode), "opcode {opcode:#04X}");
+            assert_eq!(instr.instr_byte(), opcode, "opcode {opcode:#04X}");
+        }
+    }
+
+    #[test]
+    fn existing_numbering() {
+        let map = OpcodeMap::with_std().unwrap();
+        let libs = LibSeg::default();
+
+        let code = [INSTR_NOP, 0];
+        let mut reader = Cursor::with(&code[..], &[] as &[u8], &libs);
+        let instr = Instr::<ReservedOp>::decode(&mut reader).unwrap();
+        assert_eq!(instr, Instr::Nop);
+        assert_eq!(map.family(INSTR_NOP), Some(OpFamily::Nop));
+        assert_eq!(instr.instr_byte(), INSTR_TEST);
+
+        let instr = Instr::<ReservedOp>::Digest(DigestOp::Blake3(RegS::from(0), Reg16::Reg0));
+        assert_eq!(instr.instr_byte(), INSTR_BLAKE3);
+        assert!((INSTR_ISAE_FROM..=INSTR_ISAE_TO).contains(&INSTR_BLAKE3));
+        assert_eq!(map.family(INSTR_BLAKE3), None);
+    }
+}
@@ -127,6 +127,7 @@
 //! **Control flow registers:**
 //! - Status (st0), boolean (one bit)
 //! - Cycle counter (cy0), 16 bits
+//! - Executed instruction counter (ic0), 64 bits
 //! - Instruction complexity accumulator (ca0), 16 bits
 //! - Call stack register (cs0), 3*2^16 bits (192kB block)
 //! - Call stack pointer register (cp0), 16 bits
@@ -152,6 +153,8 @@ extern crate strict_encoding;
 extern crate serde_crate as serde;
 extern crate core;
 
+#[cfg(feature = "corpus")]
+pub mod corpus;
 pub mod data;
 #[macro_use]
 pub mod isa;
@@ -159,6 +162,8 @@ pub mod library;
 pub mod reg;
 #[cfg(feature = "stl")]
 pub mod stl;
+#[cfg(feature = "testkit")]
+pub mod testkit;
 mod vm;
 
 pub use isa::Isa;
@@ -166,7 +171,12 @@ pub use isa::Isa;
 pub use library::LibArmorError;
 #[doc(hidden)]
 pub use paste::paste;
-pub use vm::Vm;
+#[cfg(feature = "sandbox")]
+pub use vm::ExecError;
+pub use vm::{
+    ExecLib, FuelUnit, LibStats, Preemption, Profiler, StreamBinding, StreamError, Vm,
+    STREAM_CHUNK_MAX_LEN,
+};
 
 /// Struct types library name.
 pub const LIB_NAME_ALUVM: &str = "AluVM";
@@ -0,0 +1,250 @@
+// Reference rust implementation of AluVM (arithmetic logic unit virtual machine).
+// To find more on AluVM please check <https://aluvm.org>
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2021-2024 by
+//     Dr Maxim Orlovsky <orlovsky@ubideco.org>
+//
+// Copyright (C) 2021-2022 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2023-2024 UBIDECO Institute. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Process-wide cache of AluVM libraries.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, OnceLock, RwLock};
+
+use amplify::ByteArray;
+
+use crate::library::{Lib, LibId};
+
+/// Number of independently locked shards of [`LibCache`].
+pub const LIB_CACHE_SHARDS: usize = 16;
+
+/// Default capacity of the [`LibCache::global`] cache, in libraries.
+pub const LIB_CACHE_DEFAULT_CAPACITY: usize = 4096;
+
+/// Usage statistics of [`LibCache`]
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug, Default)]
+pub struct CacheMetrics {
+    /// Number of lookups which have found the library in the cache
+    pub hits: u64,
+    /// Number of lookups which haven't found the library in the cache
+    pub misses: u64,
+    /// Number of libraries added to the cache
+    pub insertions: u64,
+    /// Number of libraries evicted from the cache due to capacity limits
+    pub evictions: u64,
+    /// Number of libraries currently present in the cache
+    pub len: usize,
+}
+
+#[derive(Debug)]
+struct Entry {
+    lib: Arc<Lib>,
+    last_used: AtomicU64,
+}
+
+/// Concurrent cache of libraries, keyed by their ids.
+///
+/// The cache is split into [`LIB_CACHE_SHARDS`] shards, each protected by its own `RwLock`, so
+/// lookups from multiple VMs running in parallel threads rarely contend. When a shard gets full,
+/// its least recently used library is evicted.
+#[derive(Debug)]
+pub struct LibCache {
+    shards: [RwLock<HashMap<LibId, Entry>>; LIB_CACHE_SHARDS],
+    shard_capacity: usize,
+    clock: AtomicU64,
+    hits: AtomicU64,
+    misses: AtomicU64,
+    insertions: AtomicU64,
+    evictions: AtomicU64,
+}
+
+impl Default for LibCache {
+    fn default() -> Self { LibCache::with_capacity(LIB_CACHE_DEFAULT_CAPACITY) }
+}
+
+impl LibCache {
+    /// Constructs cache holding up to `capacity` libraries (rounded up to a multiple of
+    /// [`LIB_CACHE_SHARDS`]).
+    pub fn with_capacity(capacity: usize) -> Self {
+        LibCache {
+            shards: Default::default(),
+            shard_capacity: ((capacity + LIB_CACHE_SHARDS - 1) / LIB_CACHE_SHARDS).max(1),
+            clock: AtomicU64::new(0),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+            insertions: AtomicU64::new(0),
+            evictions: AtomicU64::new(0),
+        }
+    }
+
+    /// Returns process-wide cache instance with [`LIB_CACHE_DEFAULT_CAPACITY`].
+    pub fn global() -> &'static LibCache {
+        static GLOBAL: OnceLock<LibCache> = OnceLock::new();
+        GLOBAL.get_or_init(LibCache::default)
+    }
+
+    /// Returns maximal number of libraries which may be kept by the cache.
+    #[inline]
+    pub fn capacity(&self) -> usize { self.shard_capacity * LIB_CACHE_SHARDS }
+
+    /// Looks up library by its id.
+    pub fn get(&self, id: LibId) -> Option<Arc<Lib>> {
+        let shard = self.shard(id).read().expect("poisoned lib cache lock");
+        match shard.get(&id) {
+            Some(entry) => {
+                entry.last_used.store(self.tick(), Ordering::Relaxed);
+                self.hits.fetch_add(1, Ordering::Relaxed);
+                Some(entry.lib.clone())
+            }
+            None => {
+                self.misses.fetch_add(1, Ordering::Relaxed);
+                None
+            }
+        }
+    }
+
+    /// Adds library to the cache, evicting the least recently used library from the cache shard
+    /// if it is full. If the library is already present, returns the cached instance.
+    pub fn insert(&self, lib: impl Into<Arc<Lib>>) -> Arc<Lib> {
+        let lib = lib.into();
+        let id = lib.id();
+        let mut shard = self.shard(id).write().expect("poisoned lib cache lock");
+        if let Some(entry) = shard.get(&id) {
+            return entry.lib.clone();
+        }
+        if shard.len() >= self.shard_capacity {
+            let lru = shard
+                .iter()
+                .min_by_key(|(_, entry)| entry.last_used.load(Ordering::Relaxed))
+                .map(|(id, _)| *id);
+            if let Some(lru) = lru {
+                shard.remove(&lru);
+                self.evictions.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+        shard.insert(id, Entry { lib: lib.clone(), last_used: AtomicU64::new(self.tick()) });
+        self.insertions.fetch_add(1, Ordering::Relaxed);
+        lib
+    }
+
+    /// Looks up library by its id, loading it with `load` and adding to the cache if it is not
+    /// present. Returns `None` if the library can't be loaded, or if the loaded library id does
+    /// not match the requested one.
+    pub fn get_or_load(&self, id: LibId, load: impl FnOnce() -> Option<Lib>) -> Option<Arc<Lib>> {
+        if let Some(lib) = self.get(id) {
+            return Some(lib);
+        }
+        let lib = load().filter(|lib| lib.id() == id)?;
+        Some(self.insert(lib))
+    }
+
+    /// Returns library resolver for [`Vm::exec_with`](crate::Vm::exec_with), looking up the
+    /// libraries in the cache.
+    /// Libraries which are not present in the cache are not resolved.
+    pub fn resolver(&self) -> impl Fn(LibId) -> Option<Arc<Lib>> + '_ { move |id| self.get(id) }
+
+    /// Removes library from the cache, returning it.
+    pub fn remove(&self, id: LibId) -> Option<Arc<Lib>> {
+        let mut shard = self.shard(id).write().expect("poisoned lib cache lock");
+        shard.remove(&id).map(|entry| entry.lib)
+    }
+
+    /// Removes all libraries from the cache. Does not reset the metrics.
+    pub fn clear(&self) {
+        for shard in &self.shards {
+            shard.write().expect("poisoned lib cache lock").clear();
+        }
+    }
+
+    /// Returns number of libraries in the cache.
+    pub fn len(&self) -> usize {
+        self.shards.iter().map(|shard| shard.read().expect("poisoned lib cache lock").len()).sum()
+    }
+
+    /// Detects whether the cache is empty.
+    #[inline]
+    pub fn is_empty(&self) -> bool { self.len() == 0 }
+
+    /// Returns cache usage statistics.
+    pub fn metrics(&self) -> CacheMetrics {
+        CacheMetrics {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            insertions: self.insertions.load(Ordering::Relaxed),
+            evictions: self.evictions.load(Ordering::Relaxed),
+            len: self.len(),
+        }
+    }
+
+    #[inline]
+    fn shard(&self, id: LibId) -> &RwLock<HashMap<LibId, Entry>> {
+        &self.shards[id.to_byte_array()[0] as usize % LIB_CACHE_SHARDS]
+    }
+
+    #[inline]
+    fn tick(&self) -> u64 { self.clock.fetch_add(1, Ordering::Relaxed) }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::isa::{ControlFlowOp, Instr};
+    use crate::library::LibSite;
+    use crate::Vm;
+
+    fn lib(no: u16) -> Lib { Lib::with("ALU", no.to_le_bytes().to_vec(), vec![], none!()).unwrap() }
+
+    #[test]
+    fn cache_metrics() {
+        let cache = LibCache::with_capacity(10);
+        assert_eq!(cache.capacity(), LIB_CACHE_SHARDS);
+
+        let first = lib(0);
+        assert_eq!(cache.get(first.id()), None);
+        cache.insert(first.clone());
+        assert_eq!(cache.get(first.id()).as_deref(), Some(&first));
+        assert_eq!(cache.get_or_load(first.id(), || unreachable!()).as_deref(), Some(&first));
+        assert_eq!(cache.get_or_load(lib(1).id(), || Some(lib(2))), None);
+
+        for no in 1..100 {
+            cache.insert(lib(no));
+        }
+        let metrics = cache.metrics();
+        assert!(metrics.len <= cache.capacity());
+        assert_eq!(metrics.insertions, 100);
+        assert_eq!(metrics.evictions, 100 - metrics.len as u64);
+        assert_eq!(metrics.hits, 2);
+        assert_eq!(metrics.misses, 2);
+
+        cache.clear();
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn cache_resolver() {
+        let cache = LibCache::with_capacity(10);
+        let lib = Lib::assemble::<Instr>(&[Instr::ControlFlow(ControlFlowOp::Ret)]).unwrap();
+        let id = cache.insert(lib).id();
+
+        let mut vm = Vm::<Instr>::new();
+        assert!(vm.exec_with(LibSite::with(0, id), cache.resolver(), &(), None));
+        assert_eq!(vm.registers.instr_count(), 1);
+        assert_eq!(cache.metrics().hits, 1);
+    }
+}
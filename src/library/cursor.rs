@@ -24,6 +24,7 @@
 use core::convert::TryInto;
 #[cfg(feature = "std")]
 use core::fmt::{self, Debug, Display, Formatter};
+use core::ops::Range;
 
 use amplify::num::{u1, u2, u24, u3, u4, u5, u6, u7};
 
@@ -46,6 +47,8 @@ where
     byte_pos: u16,
     data: D,
     libs: &'a LibSeg,
+    data_ref: Option<Range<usize>>,
+    lib_ref: Option<u8>,
 }
 
 #[cfg(feature = "std")]
@@ -96,7 +99,15 @@ where
     /// segment
     #[inline]
     pub fn new(bytecode: T, libs: &'a LibSeg) -> Cursor<'a, T, D> {
-        Cursor { bytecode, byte_pos: 0, bit_pos: u3::MIN, data: D::default(), libs }
+        Cursor {
+            bytecode,
+            byte_pos: 0,
+            bit_pos: u3::MIN,
+            data: D::default(),
+            libs,
+            data_ref: None,
+            lib_ref: None,
+        }
     }
 }
 
@@ -116,7 +127,15 @@ where
     pub fn with(bytecode: T, data: D, libs: &'a LibSeg) -> Cursor<'a, T, D> {
         assert!(bytecode.as_ref().len() <= CODE_SEGMENT_MAX_LEN);
         assert!(data.as_ref().len() <= DATA_SEGMENT_MAX_LEN);
-        Cursor { bytecode, byte_pos: 0, bit_pos: u3::MIN, data, libs }
+        Cursor {
+            bytecode,
+            byte_pos: 0,
+            bit_pos: u3::MIN,
+            data,
+            libs,
+            data_ref: None,
+            lib_ref: None,
+        }
     }
 
     /// Returns the current offset of the cursor
@@ -126,6 +145,17 @@ where
     #[inline]
     pub fn into_data_segment(self) -> D { self.data }
 
+    /// Returns range of the data segment which was referenced by the last value read from the
+    /// bytecode, if any, resetting it. The range may exceed the length of the data segment.
+    #[inline]
+    pub(crate) fn take_data_ref(&mut self) -> Option<Range<usize>> { self.data_ref.take() }
+
+    /// Returns index in the libs segment which was referenced by the last value read from the
+    /// bytecode, if any, resetting it. The index may exceed the length of the libs segment.
+    #[inline]
+    #[cfg_attr(not(feature = "testkit"), allow(dead_code))]
+    pub(crate) fn take_lib_ref(&mut self) -> Option<u8> { self.lib_ref.take() }
+
     #[inline]
     fn as_ref(&self) -> &[u8] { self.bytecode.as_ref() }
 
@@ -331,21 +361,25 @@ where
 
     #[inline]
     fn read_lib(&mut self) -> Result<LibId, CodeEofError> {
-        Ok(self.libs.at(self.read_u8()?).unwrap_or_default())
+        let index = self.read_u8()?;
+        self.lib_ref = Some(index);
+        Ok(self.libs.at(index).unwrap_or_default())
     }
 
     fn read_data(&mut self) -> Result<(&[u8], bool), CodeEofError> {
         let offset = self.read_u16()? as usize;
         let end = offset + self.read_u16()? as usize;
-        let max = DATA_SEGMENT_MAX_LEN;
-        let st0 = end > self.data.as_ref().len();
-        let data = &self.data.as_ref()[offset.min(max)..end.min(max)];
+        self.data_ref = Some(offset..end);
+        let len = self.data.as_ref().len();
+        let st0 = end > len;
+        let data = &self.data.as_ref()[offset.min(len)..end.min(len)];
         Ok((data, st0))
     }
 
     fn read_number(&mut self, reg: impl NumericRegister) -> Result<Number, CodeEofError> {
         let offset = self.read_u16()? as usize;
         let end = offset + reg.bytes() as usize;
+        self.data_ref = Some(offset..end);
         if end > self.data.as_ref().len() {
             return Err(CodeEofError);
         }
@@ -504,6 +538,16 @@ mod tests {
         assert!(cursor.read_u8().is_err());
     }
 
+    #[test]
+    fn read_data_out_of_range() {
+        let libseg = LibSeg::default();
+        let data = [0xA5u8; 4];
+        let code = [0x02, 0x00, 0x04, 0x00, 0x00, 0x10, 0x01, 0x00];
+        let mut cursor = Cursor::with(&code[..], &data[..], &libseg);
+        assert_eq!(cursor.read_data().unwrap(), (&data[2..], true));
+        assert_eq!(cursor.read_data().unwrap(), (&[] as &[u8], true));
+    }
+
     #[test]
     fn write() {
         let libseg = LibSeg::default();
@@ -0,0 +1,386 @@
+// Reference rust implementation of AluVM (arithmetic logic unit virtual machine).
+// To find more on AluVM please check <https://aluvm.org>
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2021-2024 by
+//     Dr Maxim Orlovsky <orlovsky@ubideco.org>
+//
+// Copyright (C) 2021-2022 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2023-2024 UBIDECO Institute. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Pre-decoded representation of library code, with optional fusion of adjacent instructions
+//! into superinstructions.
+
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+
+use crate::isa::{
+    ArithmeticOp, Bytecode, ControlFlowOp, ExecStep, Instr, InstructionSet, MoveOp, PutOp,
+};
+use crate::library::{Cursor, Lib, LibId, LibSite, Read};
+use crate::reg::CoreRegs;
+use crate::vm::Preemption;
+
+/// Kinds of adjacent instruction pairs which are fused into superinstructions.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug, Display)]
+#[display(doc_comments)]
+pub enum Fusion {
+    /// comparison followed by a conditional jump
+    CmpJif,
+    /// assignment of a value to a register followed by an addition
+    PutAdd,
+    /// duplication of a register value followed by hashing
+    DupHash,
+}
+
+impl Fusion {
+    /// Detects whether a pair of adjacent instructions can be fused into a superinstruction.
+    pub fn detect<Extension>(first: &Instr<Extension>, second: &Instr<Extension>) -> Option<Fusion>
+    where
+        Extension: InstructionSet,
+    {
+        match (first, second) {
+            (Instr::Cmp(_), Instr::ControlFlow(ControlFlowOp::Jif(_))) => Some(Fusion::CmpJif),
+            (
+                Instr::Put(PutOp::PutA(..) | PutOp::PutF(..) | PutOp::PutR(..)),
+                Instr::Arithmetic(ArithmeticOp::AddA(..) | ArithmeticOp::AddF(..)),
+            ) => Some(Fusion::PutAdd),
+            (
+                Instr::Move(MoveOp::DupA(..) | MoveOp::DupF(..) | MoveOp::DupR(..)),
+                Instr::Digest(_),
+            ) => Some(Fusion::DupHash),
+            _ => None,
+        }
+    }
+}
+
+/// Library code decoded into instructions ahead of execution.
+///
+/// Execution of the decoded library is equivalent to [`Lib::exec`], but skips bytecode decoding
+/// at each step. Jumps to offsets which are not instruction boundaries of the sequentially decoded
+/// code fall back to the bytecode interpreter.
+///
+/// After [`DecodedLib::fuse`] is called, common pairs of adjacent instructions (see [`Fusion`])
+/// are executed as a single superinstruction: a dedicated handler calls both operations
+/// directly, without returning to the dispatch loop and matching [`Instr`] variants between
+/// them. The wire format of the library is not affected.
+#[derive(Clone, Debug)]
+pub struct DecodedLib<Extension>
+where
+    Extension: InstructionSet,
+{
+    lib: Lib,
+    id: LibId,
+    code: Vec<Instr<Extension>>,
+    offsets: Vec<u16>,
+    index: BTreeMap<u16, usize>,
+    fusions: Vec<Option<Fusion>>,
+}
+
+impl<Extension> DecodedLib<Extension>
+where
+    Extension: InstructionSet,
+{
+    /// Decodes library code. Decoding stops at the first instruction which can't be decoded; if
+    /// the execution reaches it, it halts in the same way as [`Lib::exec`] does.
+    pub fn with(lib: &Lib) -> Self {
+        let mut code = Vec::new();
+        let mut offsets = Vec::new();
+        let mut index = BTreeMap::new();
+        let mut cursor = Cursor::with(&lib.code, &lib.data, &lib.libs);
+        while !cursor.is_eof() {
+            let pos = cursor.pos();
+            match Instr::<Extension>::decode(&mut cursor) {
+                Ok(instr) => {
+                    index.insert(pos, code.len());
+                    offsets.push(pos);
+                    code.push(instr);
+                }
+                Err(_) => break,
+            }
+        }
+        let fusions = vec![None; code.len()];
+        DecodedLib { lib: lib.clone(), id: lib.id(), code, offsets, index, fusions }
+    }
+
+    /// Returns id of the decoded library.
+    #[inline]
+    pub fn id(&self) -> LibId { self.id }
+
+    /// Returns the original library.
+    #[inline]
+    pub fn as_lib(&self) -> &Lib { &self.lib }
+
+    /// Runs superinstruction fusion pass over the decoded code, returning the number of fused
+    /// instruction pairs.
+    ///
+    /// Instructions following a fused one are kept in place, so jumps into the middle of a
+    /// superinstruction remain valid.
+    pub fn fuse(&mut self) -> usize {
+        let mut count = 0;
+        let mut idx = 0;
+        while idx + 1 < self.code.len() {
+            self.fusions[idx] = Fusion::detect(&self.code[idx], &self.code[idx + 1]);
+            if self.fusions[idx].is_some() {
+                count += 1;
+                idx += 2;
+            } else {
+                idx += 1;
+            }
+        }
+        count
+    }
+
+    /// Iterates over superinstructions, returning their code offset and fusion kind.
+    pub fn superinstructions(&self) -> impl Iterator<Item = (u16, Fusion)> + '_ {
+        self.fusions
+            .iter()
+            .zip(&self.offsets)
+            .filter_map(|(fusion, pos)| fusion.map(|fusion| (*pos, fusion)))
+    }
+
+    /// Executes library code starting at entrypoint.
+    ///
+    /// # Returns
+    ///
+    /// Location for the external code jump, if any
+    pub fn exec(
+        &self,
+        entrypoint: u16,
+        registers: &mut CoreRegs,
+        context: &Extension::Context<'_>,
+    ) -> Option<LibSite> {
+        self.exec_inner(entrypoint, registers, context, None)
+    }
+
+    pub(crate) fn exec_inner(
+        &self,
+        entrypoint: u16,
+        registers: &mut CoreRegs,
+        context: &Extension::Context<'_>,
+        mut preemption: Option<&mut Preemption>,
+    ) -> Option<LibSite> {
+        let mut idx = match self.index.get(&entrypoint) {
+            Some(idx) => *idx,
+            None => return self.fallback(entrypoint, registers, context, preemption),
+        };
+        loop {
+            // Code may end without a terminating instruction or be cut at the first undecodable
+            // instruction; in both cases `Lib::exec` halts without changing `st0`
+            let next = match *self.fusions.get(idx)? {
+                Some(fusion) => {
+                    let next = self.exec_fused(idx, fusion, registers)?;
+                    idx += 1;
+                    next
+                }
+                None => self.step(idx, registers, context)?,
+            };
+            if let Some(preemption) = preemption.as_deref_mut() {
+                if !preemption.check(registers) {
+                    registers.st0 = false;
+                    return None;
+                }
+            }
+            match next {
+                ExecStep::Stop => return None,
+                ExecStep::Next => idx += 1,
+                ExecStep::Jump(pos) => match self.index.get(&pos) {
+                    Some(target) => idx = *target,
+                    None => return self.fallback(pos, registers, context, preemption),
+                },
+                ExecStep::Call(site) => return Some(site),
+            }
+        }
+    }
+
+    /// Executes a single instruction. Returns `None` if the execution must halt.
+    #[inline]
+    fn step(
+        &self,
+        idx: usize,
+        registers: &mut CoreRegs,
+        context: &Extension::Context<'_>,
+    ) -> Option<ExecStep> {
+        let instr = self.code.get(idx)?;
+        if !registers.check_src_regs(instr) {
+            return None;
+        }
+        let next = instr.exec(registers, LibSite::with(self.offsets[idx], self.id), context);
+        if !registers.acc_instr_complexity(instr.complexity()) {
+            return None;
+        }
+        Some(next)
+    }
+
+    /// Executes superinstruction made of the instruction at `idx` and the one following it. Returns
+    /// `None` if the execution must halt.
+    fn exec_fused(&self, idx: usize, fusion: Fusion, registers: &mut CoreRegs) -> Option<ExecStep> {
+        let first = LibSite::with(self.offsets[idx], self.id);
+        let second = LibSite::with(self.offsets[idx + 1], self.id);
+        // The first operation of each of the superinstructions always proceeds to the next one
+        match (fusion, &self.code[idx], &self.code[idx + 1]) {
+            (Fusion::CmpJif, Instr::Cmp(cmp), Instr::ControlFlow(jif)) => {
+                exec_op(cmp, registers, first)?;
+                exec_op(jif, registers, second)
+            }
+            (Fusion::PutAdd, Instr::Put(put), Instr::Arithmetic(add)) => {
+                exec_op(put, registers, first)?;
+                exec_op(add, registers, second)
+            }
+            (Fusion::DupHash, Instr::Move(dup), Instr::Digest(hash)) => {
+                exec_op(dup, registers, first)?;
+                exec_op(hash, registers, second)
+            }
+            _ => unreachable!("superinstruction {} doesn't match the fused instructions", fusion),
+        }
+    }
+
+    fn fallback(
+        &self,
+        pos: u16,
+        registers: &mut CoreRegs,
+        context: &Extension::Context<'_>,
+        preemption: Option<&mut Preemption>,
+    ) -> Option<LibSite> {
+        self.lib.exec_inner::<Instr<Extension>>(pos, registers, context, preemption)
+    }
+}
+
+/// Executes a core operation which is a part of a superinstruction. Returns `None` if the
+/// execution must halt.
+#[inline]
+fn exec_op<Op>(op: &Op, registers: &mut CoreRegs, site: LibSite) -> Option<ExecStep>
+where
+    Op: for<'ctx> InstructionSet<Context<'ctx> = ()>,
+{
+    if !registers.check_src_regs(op) {
+        return None;
+    }
+    let next = op.exec(registers, site, &());
+    if !registers.acc_instr_complexity(op.complexity()) {
+        return None;
+    }
+    Some(next)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::data::{ByteStr, MaybeNumber, Number};
+    use crate::isa::{ArithmeticOp, BytesOp, CmpOp, DigestOp, IntFlags, ReservedOp, SignFlag};
+    use crate::reg::{Reg16, Reg32, RegA, RegR, RegS};
+
+    fn check_exec(lib: &Lib, decoded: &DecodedLib<ReservedOp>) -> CoreRegs {
+        let mut regs = CoreRegs::default();
+        let mut expected = CoreRegs::default();
+        assert_eq!(decoded.exec(0, &mut regs, &()), None);
+        assert_eq!(lib.exec::<Instr<ReservedOp>>(0, &mut expected, &()), None);
+        assert_eq!(regs.instr_count(), expected.instr_count());
+        assert_eq!(regs.complexity_acc(), expected.complexity_acc());
+        assert_eq!(regs.st0, expected.st0);
+        regs
+    }
+
+    #[test]
+    fn fused_exec() {
+        let put = |idx, val: u64| Instr::Put(PutOp::PutA(RegA::A64, idx, Box::new(val.into())));
+        let mut code: Vec<Instr<ReservedOp>> =
+            vec![put(Reg32::Reg0, 0), put(Reg32::Reg1, 1), put(Reg32::Reg2, 100)];
+        // Jumping into the middle of `put` + `add` superinstruction
+        let start = Lib::assemble(&code).unwrap().code.len() as u16;
+        code.extend([
+            Instr::Arithmetic(ArithmeticOp::AddA(
+                IntFlags::unsigned_checked(),
+                RegA::A64,
+                Reg32::Reg1,
+                Reg32::Reg0,
+            )),
+            Instr::Cmp(CmpOp::LtA(SignFlag::Unsigned, RegA::A64, Reg32::Reg0, Reg32::Reg2)),
+            Instr::ControlFlow(ControlFlowOp::Jif(start)),
+            // Loop exits once the comparison fails, so the status must be restored
+            Instr::Cmp(CmpOp::StInv),
+            Instr::ControlFlow(ControlFlowOp::Ret),
+        ]);
+        let lib = Lib::assemble(&code).unwrap();
+
+        let mut decoded = DecodedLib::<ReservedOp>::with(&lib);
+        assert_eq!(decoded.fuse(), 2);
+        let fusions = decoded.superinstructions().map(|(_, fusion)| fusion).collect::<Vec<_>>();
+        assert_eq!(fusions, vec![Fusion::PutAdd, Fusion::CmpJif]);
+
+        let regs = check_exec(&lib, &decoded);
+        assert_eq!(regs.get_n(RegA::A64, Reg32::Reg0), MaybeNumber::from(100u64));
+        assert!(regs.st0);
+    }
+
+    #[test]
+    fn no_terminator() {
+        let put = |idx, val: u64| Instr::Put(PutOp::PutA(RegA::A64, idx, Box::new(val.into())));
+        let code: Vec<Instr<ReservedOp>> = vec![
+            put(Reg32::Reg0, 1),
+            put(Reg32::Reg1, 2),
+            Instr::Arithmetic(ArithmeticOp::AddA(
+                IntFlags::unsigned_checked(),
+                RegA::A64,
+                Reg32::Reg0,
+                Reg32::Reg1,
+            )),
+        ];
+        let lib = Lib::assemble(&code).unwrap();
+        let mut decoded = DecodedLib::<ReservedOp>::with(&lib);
+        assert_eq!(decoded.fuse(), 1);
+        let regs = check_exec(&lib, &decoded);
+        assert_eq!(regs.get_n(RegA::A64, Reg32::Reg1), MaybeNumber::from(3u64));
+
+        // Code cut in the middle of the last instruction
+        let mut bytecode = lib.code.to_vec();
+        bytecode.push(lib.code[0]);
+        let truncated =
+            Lib::with(&lib.isae_segment(), bytecode, lib.data.to_vec(), lib.libs.clone()).unwrap();
+        let mut decoded = DecodedLib::<ReservedOp>::with(&truncated);
+        assert_eq!(decoded.fuse(), 1);
+        let regs = check_exec(&truncated, &decoded);
+        assert_eq!(regs.instr_count(), 3);
+    }
+
+    #[test]
+    fn fused_dup_hash() {
+        let code: Vec<Instr<ReservedOp>> = vec![
+            Instr::Put(PutOp::PutR(
+                RegR::R256,
+                Reg32::Reg0,
+                Box::new(Number::from_slice([7u8; 32]).into()),
+            )),
+            Instr::Bytes(BytesOp::Put(RegS::from(0), Box::new(ByteStr::with(b"aluvm")), false)),
+            Instr::Move(MoveOp::DupR(RegR::R256, Reg32::Reg0, Reg32::Reg1)),
+            Instr::Digest(DigestOp::Sha256(RegS::from(0), Reg16::Reg2)),
+            Instr::ControlFlow(ControlFlowOp::Ret),
+        ];
+        let lib = Lib::assemble(&code).unwrap();
+
+        let mut decoded = DecodedLib::<ReservedOp>::with(&lib);
+        assert_eq!(decoded.fuse(), 1);
+        assert_eq!(
+            decoded.superinstructions().map(|(_, fusion)| fusion).collect::<Vec<_>>(),
+            vec![Fusion::DupHash]
+        );
+
+        let regs = check_exec(&lib, &decoded);
+        assert!(regs.st0);
+        assert_eq!(regs.get_n(RegR::R256, Reg32::Reg1), regs.get_n(RegR::R256, Reg32::Reg0));
+        assert!(regs.get_n(RegR::R256, Reg32::Reg2).is_some());
+    }
+}
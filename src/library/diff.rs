@@ -0,0 +1,615 @@
+// Reference rust implementation of AluVM (arithmetic logic unit virtual machine).
+// To find more on AluVM please check <https://aluvm.org>
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2021-2024 by
+//     Dr Maxim Orlovsky <orlovsky@ubideco.org>
+//
+// Copyright (C) 2021-2022 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2023-2024 UBIDECO Institute. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Instruction-level comparison of two libraries.
+
+#[cfg(all(feature = "alloc", not(feature = "std")))]
+use alloc::collections::BTreeSet;
+#[cfg(all(feature = "alloc", not(feature = "std")))]
+use alloc::string::String;
+#[cfg(all(feature = "alloc", not(feature = "std")))]
+use alloc::vec::Vec;
+use core::fmt::{self, Display, Formatter};
+use core::ops::Range;
+#[cfg(feature = "std")]
+use std::collections::BTreeSet;
+
+use amplify::hex::ToHex;
+
+use super::{CodeEofError, Cursor, Lib, LibId, Read};
+use crate::isa::InstructionSet;
+
+/// Difference between two libraries in a single instruction.
+///
+/// Offsets are given in bytes from the beginning of the code segment of the old (`old`) and new
+/// (`new`) library.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub enum InstrDiff<Isa>
+where
+    Isa: InstructionSet,
+{
+    /// Instruction is present in both libraries
+    Same {
+        /// Offset of the instruction in the old library
+        old: u16,
+        /// Offset of the instruction in the new library
+        new: u16,
+        /// The instruction
+        instr: Isa,
+    },
+
+    /// Instruction is present only in the new library
+    Inserted {
+        /// Offset of the instruction in the new library
+        new: u16,
+        /// The inserted instruction
+        instr: Isa,
+    },
+
+    /// Instruction is present only in the old library
+    Deleted {
+        /// Offset of the instruction in the old library
+        old: u16,
+        /// The deleted instruction
+        instr: Isa,
+    },
+
+    /// Instruction has the same opcode in both libraries, but different operands
+    Changed {
+        /// Offset of the instruction in the old library
+        old: u16,
+        /// Offset of the instruction in the new library
+        new: u16,
+        /// Instruction from the old library
+        from: Isa,
+        /// Instruction from the new library
+        to: Isa,
+    },
+}
+
+impl<Isa> InstrDiff<Isa>
+where
+    Isa: InstructionSet,
+{
+    /// Detects whether the entry represents a difference between the libraries.
+    #[inline]
+    pub fn is_change(&self) -> bool { !matches!(self, InstrDiff::Same { .. }) }
+}
+
+impl<Isa> Display for InstrDiff<Isa>
+where
+    Isa: InstructionSet,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            InstrDiff::Same { old, new, instr } => write!(f, "  {old:#06X} {new:#06X}  {instr}"),
+            InstrDiff::Inserted { new, instr } => write!(f, "+        {new:#06X}  {instr}"),
+            InstrDiff::Deleted { old, instr } => write!(f, "- {old:#06X}         {instr}"),
+            InstrDiff::Changed { old, new, from, to } => {
+                write!(f, "~ {old:#06X} {new:#06X}  {from} => {to}")
+            }
+        }
+    }
+}
+
+/// Difference between data segments of two libraries in a continuous fragment.
+///
+/// Offsets are given in bytes from the beginning of the data segment of the old (`old`) and new
+/// (`new`) library.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct DataDiff {
+    /// Offset of the fragment in the old library
+    pub old: u16,
+    /// Offset of the fragment in the new library
+    pub new: u16,
+    /// Data from the old library, empty if the fragment was inserted
+    pub from: Vec<u8>,
+    /// Data from the new library, empty if the fragment was deleted
+    pub to: Vec<u8>,
+}
+
+impl Display for DataDiff {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let (old, new) = (self.old, self.new);
+        let (from, to) = (self.from.to_hex(), self.to.to_hex());
+        match (from.is_empty(), to.is_empty()) {
+            (true, _) => write!(f, "+        {new:#06X}  data {to}"),
+            (_, true) => write!(f, "- {old:#06X}         data {from}"),
+            _ => write!(f, "~ {old:#06X} {new:#06X}  data {from} => {to}"),
+        }
+    }
+}
+
+/// Structured difference between two libraries, produced by [`Lib::diff`].
+///
+/// Its [`Display`] implementation produces a human-readable representation of the difference,
+/// suitable for audit of changes between two versions of a published library.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct LibDiff<Isa>
+where
+    Isa: InstructionSet,
+{
+    /// Id of the old library
+    pub old_id: LibId,
+    /// Id of the new library
+    pub new_id: LibId,
+    /// ISA extensions segments of the old and new library, if they differ
+    pub isae: Option<(String, String)>,
+    /// Libraries referenced only by the old library
+    pub libs_removed: BTreeSet<LibId>,
+    /// Libraries referenced only by the new library
+    pub libs_added: BTreeSet<LibId>,
+    /// Byte-level difference of the data segments
+    pub data: Vec<DataDiff>,
+    /// Instruction-level difference of the code segments
+    pub code: Vec<InstrDiff<Isa>>,
+}
+
+impl<Isa> LibDiff<Isa>
+where
+    Isa: InstructionSet,
+{
+    /// Detects whether the libraries are equivalent.
+    #[inline]
+    pub fn is_empty(&self) -> bool { self.old_id == self.new_id }
+
+    /// Iterates over the instructions which differ between the libraries.
+    pub fn changes(&self) -> impl Iterator<Item = &InstrDiff<Isa>> {
+        self.code.iter().filter(|diff| diff.is_change())
+    }
+}
+
+impl<Isa> Display for LibDiff<Isa>
+where
+    Isa: InstructionSet,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        writeln!(f, "--- {}", self.old_id)?;
+        writeln!(f, "+++ {}", self.new_id)?;
+        if let Some((old, new)) = &self.isae {
+            writeln!(f, "isae: {old} => {new}")?;
+        }
+        for lib in &self.libs_removed {
+            writeln!(f, "- lib {lib}")?;
+        }
+        for lib in &self.libs_added {
+            writeln!(f, "+ lib {lib}")?;
+        }
+        for diff in &self.data {
+            writeln!(f, "{diff}")?;
+        }
+        for diff in &self.code {
+            writeln!(f, "{diff}")?;
+        }
+        Ok(())
+    }
+}
+
+impl Lib {
+    /// Compares the library with another library at the instruction level.
+    ///
+    /// Instructions and data segment bytes are matched using the shortest edit script (computed
+    /// with the linear-space Myers algorithm); adjacent deletion and insertion of the instructions
+    /// with the same opcode are reported as a change in the instruction operands. Since the data
+    /// referenced by the instructions is decoded as their operands, changes in constants used by
+    /// the code are reported both in the code and data segment differences.
+    ///
+    /// # Errors
+    ///
+    /// If any of the libraries can't be disassembled with the provided instruction set.
+    pub fn diff<Isa>(&self, other: &Lib) -> Result<LibDiff<Isa>, CodeEofError>
+    where
+        Isa: InstructionSet + PartialEq,
+    {
+        let old = self.disassemble_with_offsets::<Isa>()?;
+        let new = other.disassemble_with_offsets::<Isa>()?;
+
+        let old_isae = self.isae_segment();
+        let new_isae = other.isae_segment();
+        let isae = if old_isae != new_isae { Some((old_isae, new_isae)) } else { None };
+
+        let old_libs = self.libs_segment().iter().copied().collect::<BTreeSet<_>>();
+        let new_libs = other.libs_segment().iter().copied().collect::<BTreeSet<_>>();
+
+        Ok(LibDiff {
+            old_id: self.id(),
+            new_id: other.id(),
+            isae,
+            libs_removed: old_libs.difference(&new_libs).copied().collect(),
+            libs_added: new_libs.difference(&old_libs).copied().collect(),
+            data: diff_data(&self.data, &other.data),
+            code: diff_code(old, new),
+        })
+    }
+
+    fn disassemble_with_offsets<Isa>(&self) -> Result<Vec<(u16, Isa)>, CodeEofError>
+    where
+        Isa: InstructionSet,
+    {
+        let mut code = Vec::new();
+        let mut reader = Cursor::with(&self.code, &self.data, &self.libs);
+        while !reader.is_eof() {
+            let pos = reader.pos();
+            code.push((pos, Isa::decode(&mut reader)?));
+        }
+        Ok(code)
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+enum DiffOp {
+    Keep,
+    Delete,
+    Insert,
+}
+
+/// Computes the shortest edit script transforming a sequence of `n` elements into a sequence of
+/// `m` elements, where `eq(i, j)` compares `i`-th element of the first sequence with `j`-th
+/// element of the second one.
+///
+/// Uses the linear-space variant of the Myers algorithm, taking O((n + m) * d) time and O(n + m)
+/// memory for `d` differing elements.
+fn diff_ops(n: usize, m: usize, eq: impl Fn(usize, usize) -> bool) -> Vec<DiffOp> {
+    let mut ops = Vec::with_capacity(n + m);
+    diff_range(&eq, 0..n, 0..m, &mut ops);
+    ops
+}
+
+fn diff_range(
+    eq: &impl Fn(usize, usize) -> bool,
+    old: Range<usize>,
+    new: Range<usize>,
+    ops: &mut Vec<DiffOp>,
+) {
+    let prefix = old.clone().zip(new.clone()).take_while(|(i, j)| eq(*i, *j)).count();
+    let (old, new) = (old.start + prefix..old.end, new.start + prefix..new.end);
+    let suffix = old.clone().rev().zip(new.clone().rev()).take_while(|(i, j)| eq(*i, *j)).count();
+    let (old, new) = (old.start..old.end - suffix, new.start..new.end - suffix);
+
+    ops.resize(ops.len() + prefix, DiffOp::Keep);
+    match bisect(eq, old.clone(), new.clone()) {
+        Some((x, y)) => {
+            diff_range(eq, old.start..x, new.start..y, ops);
+            diff_range(eq, x..old.end, y..new.end, ops);
+        }
+        None => {
+            ops.resize(ops.len() + old.len(), DiffOp::Delete);
+            ops.resize(ops.len() + new.len(), DiffOp::Insert);
+        }
+    }
+    ops.resize(ops.len() + suffix, DiffOp::Keep);
+}
+
+/// Finds the middle snake of the optimal edit path, walking the edit graph from both its ends, and
+/// returns a point splitting the path. Returns `None` if the sequences are empty or have no common
+/// elements.
+fn bisect(
+    eq: &impl Fn(usize, usize) -> bool,
+    old: Range<usize>,
+    new: Range<usize>,
+) -> Option<(usize, usize)> {
+    let (n, m) = (old.len() as isize, new.len() as isize);
+    if n == 0 || m == 0 {
+        return None;
+    }
+    let eq = |x: isize, y: isize| eq(old.start + x as usize, new.start + y as usize);
+    let split = |x: isize, y: isize| Some((old.start + x as usize, new.start + y as usize));
+
+    // Furthest reaching x coordinates on each of the diagonals; the backward walk uses
+    // coordinates counted from the end of the sequences
+    let max_d = (n + m + 1) / 2;
+    let idx = |k: isize| (max_d + k) as usize;
+    let mut forward = vec![-1isize; 2 * max_d as usize + 2];
+    let mut backward = forward.clone();
+    forward[idx(1)] = 0;
+    backward[idx(1)] = 0;
+    let delta = n - m;
+    // The paths can overlap only on the forward walk if the delta is odd, and only on the backward
+    // walk otherwise
+    let front = delta % 2 != 0;
+    // Diagonals falling out of the edit graph are excluded from the consequent walks
+    let (mut k1start, mut k1end, mut k2start, mut k2end) = (0, 0, 0, 0);
+    for d in 0..max_d {
+        for k1 in (-d + k1start..=d - k1end).step_by(2) {
+            let mut x1 = if k1 == -d || (k1 != d && forward[idx(k1 - 1)] < forward[idx(k1 + 1)]) {
+                forward[idx(k1 + 1)]
+            } else {
+                forward[idx(k1 - 1)] + 1
+            };
+            let mut y1 = x1 - k1;
+            while x1 < n && y1 < m && eq(x1, y1) {
+                x1 += 1;
+                y1 += 1;
+            }
+            forward[idx(k1)] = x1;
+            if x1 > n {
+                k1end += 2;
+            } else if y1 > m {
+                k1start += 2;
+            } else if front {
+                let k2 = delta - k1;
+                if k2.abs() <= max_d && backward[idx(k2)] != -1 && x1 >= n - backward[idx(k2)] {
+                    return split(x1, y1);
+                }
+            }
+        }
+
+        for k2 in (-d + k2start..=d - k2end).step_by(2) {
+            let mut x2 = if k2 == -d || (k2 != d && backward[idx(k2 - 1)] < backward[idx(k2 + 1)]) {
+                backward[idx(k2 + 1)]
+            } else {
+                backward[idx(k2 - 1)] + 1
+            };
+            let mut y2 = x2 - k2;
+            while x2 < n && y2 < m && eq(n - x2 - 1, m - y2 - 1) {
+                x2 += 1;
+                y2 += 1;
+            }
+            backward[idx(k2)] = x2;
+            if x2 > n {
+                k2end += 2;
+            } else if y2 > m {
+                k2start += 2;
+            } else if !front {
+                let k1 = delta - k2;
+                if k1.abs() <= max_d && forward[idx(k1)] != -1 {
+                    let x1 = forward[idx(k1)];
+                    if x1 >= n - x2 {
+                        return split(x1, x1 - k1);
+                    }
+                }
+            }
+        }
+    }
+    None
+}
+
+/// Iterates over the edit script, yielding runs of the kept elements and the changed fragments
+/// (with deletions preceding insertions) as numbers of the old and new elements.
+fn diff_runs(ops: &[DiffOp]) -> impl Iterator<Item = (bool, usize, usize)> + '_ {
+    let mut ops = ops.iter().peekable();
+    core::iter::from_fn(move || {
+        let keep = *ops.peek()? == &DiffOp::Keep;
+        let (mut deleted, mut inserted) = (0, 0);
+        while let Some(op) = ops.next_if(|op| (**op == DiffOp::Keep) == keep) {
+            match op {
+                DiffOp::Keep | DiffOp::Delete => deleted += 1,
+                DiffOp::Insert => inserted += 1,
+            }
+        }
+        Some(if keep { (true, deleted, deleted) } else { (false, deleted, inserted) })
+    })
+}
+
+fn diff_code<Isa>(old: Vec<(u16, Isa)>, new: Vec<(u16, Isa)>) -> Vec<InstrDiff<Isa>>
+where
+    Isa: InstructionSet + PartialEq,
+{
+    let ops = diff_ops(old.len(), new.len(), |i, j| old[i].1 == new[j].1);
+
+    let mut old = old.into_iter();
+    let mut new = new.into_iter();
+    let mut diff = Vec::with_capacity(ops.len());
+    for (keep, deleted, inserted) in diff_runs(&ops) {
+        if keep {
+            for ((old, instr), (new, _)) in old.by_ref().zip(new.by_ref()).take(deleted) {
+                diff.push(InstrDiff::Same { old, new, instr });
+            }
+            continue;
+        }
+        for (old, instr) in old.by_ref().take(deleted) {
+            diff.push(InstrDiff::Deleted { old, instr });
+        }
+        for instr in new.by_ref().take(inserted) {
+            push_insertion(&mut diff, instr);
+        }
+    }
+    diff
+}
+
+fn push_insertion<Isa>(diff: &mut Vec<InstrDiff<Isa>>, (new, to): (u16, Isa))
+where
+    Isa: InstructionSet,
+{
+    // Deletion followed by insertion of the instruction with the same opcode is reported as a
+    // change of the instruction operands
+    match diff.pop() {
+        Some(InstrDiff::Deleted { old, instr: from }) if from.instr_byte() == to.instr_byte() => {
+            diff.push(InstrDiff::Changed { old, new, from, to })
+        }
+        last => {
+            diff.extend(last);
+            diff.push(InstrDiff::Inserted { new, instr: to })
+        }
+    }
+}
+
+fn diff_data(old: &[u8], new: &[u8]) -> Vec<DataDiff> {
+    let ops = diff_ops(old.len(), new.len(), |i, j| old[i] == new[j]);
+
+    let (mut i, mut j) = (0, 0);
+    let mut diff = vec![];
+    for (keep, deleted, inserted) in diff_runs(&ops) {
+        if !keep {
+            diff.push(DataDiff {
+                old: i as u16,
+                new: j as u16,
+                from: old[i..i + deleted].to_vec(),
+                to: new[j..j + inserted].to_vec(),
+            });
+        }
+        i += deleted;
+        j += inserted;
+    }
+    diff
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::data::{MaybeNumber, Number};
+    use crate::isa::{ArithmeticOp, ConstOp, ControlFlowOp, Instr, IntFlags, PutOp};
+    use crate::reg::{Reg32, RegA};
+
+    fn lcs_len(a: &[u8], b: &[u8]) -> usize {
+        let mut row = vec![0usize; b.len() + 1];
+        for x in a {
+            let mut diag = 0;
+            for (j, y) in b.iter().enumerate() {
+                let up = row[j + 1];
+                row[j + 1] = if x == y { diag + 1 } else { up.max(row[j]) };
+                diag = up;
+            }
+        }
+        row[b.len()]
+    }
+
+    fn check_script(a: &[u8], b: &[u8]) {
+        let ops = diff_ops(a.len(), b.len(), |i, j| a[i] == b[j]);
+        let (mut i, mut j) = (0, 0);
+        for op in &ops {
+            match op {
+                DiffOp::Keep => {
+                    assert_eq!(a[i], b[j]);
+                    i += 1;
+                    j += 1;
+                }
+                DiffOp::Delete => i += 1,
+                DiffOp::Insert => j += 1,
+            }
+        }
+        assert_eq!((i, j), (a.len(), b.len()));
+        let kept = ops.iter().filter(|op| **op == DiffOp::Keep).count();
+        assert_eq!(kept, lcs_len(a, b), "{:?} {:?}", a, b);
+    }
+
+    #[test]
+    fn shortest_script() {
+        check_script(b"", b"");
+        check_script(b"abc", b"");
+        check_script(b"", b"abc");
+        check_script(b"abcabba", b"cbabac");
+        check_script(b"abc", b"xyz");
+
+        let mut seed = 0x2545_F491_4F6C_DD1Du64;
+        let mut next = |len: u64| {
+            seed ^= seed << 13;
+            seed ^= seed >> 7;
+            seed ^= seed << 17;
+            seed % len
+        };
+        for _ in 0..500 {
+            let (n, m) = (next(24) as usize, next(24) as usize);
+            let a = (0..n).map(|_| next(4) as u8).collect::<Vec<_>>();
+            let b = (0..m).map(|_| next(4) as u8).collect::<Vec<_>>();
+            check_script(&a, &b);
+        }
+    }
+
+    #[test]
+    fn large_data_diff() {
+        // Quadratic table for segments of this size would take gigabytes of memory
+        let old = (0..u16::MAX).map(|i| (i % 251) as u8).collect::<Vec<_>>();
+        let mut new = old.clone();
+        new[100] ^= 0xFF;
+        new.drain(30_000..30_010);
+        new.extend([1, 2, 3]);
+        let diff = diff_data(&old, &new);
+        assert_eq!(diff, vec![
+            DataDiff { old: 100, new: 100, from: vec![old[100]], to: vec![new[100]] },
+            DataDiff { old: 30_000, new: 30_000, from: old[30_000..30_010].to_vec(), to: vec![] },
+            DataDiff { old: u16::MAX, new: u16::MAX - 10, from: vec![], to: vec![1, 2, 3] },
+        ]);
+    }
+
+    #[test]
+    fn data_diff() {
+        let put = |val: u64| {
+            Instr::Put(PutOp::PutA(
+                RegA::A64,
+                Reg32::Reg0,
+                Box::new(MaybeNumber::from(Number::from(val))),
+            ))
+        };
+        let old_lib = Lib::assemble::<Instr>(&[put(0x1111), put(0x2222)]).unwrap();
+        let new_lib = Lib::assemble::<Instr>(&[put(0x1111), put(0x3333)]).unwrap();
+
+        let diff = old_lib.diff::<Instr>(&new_lib).unwrap();
+        assert_eq!(diff.changes().count(), 1);
+        assert_eq!(diff.data, vec![DataDiff {
+            old: 8,
+            new: 8,
+            from: vec![0x22, 0x22],
+            to: vec![0x33, 0x33],
+        }]);
+        assert!(diff.to_string().contains("~ 0x0008 0x0008  data 2222 => 3333\n"));
+    }
+
+    #[test]
+    fn instr_diff() {
+        let old: Vec<Instr> = vec![
+            Instr::Const(ConstOp::OneA(RegA::A64, Reg32::Reg0)),
+            Instr::Const(ConstOp::ZeroA(RegA::A64, Reg32::Reg1)),
+            Instr::Arithmetic(ArithmeticOp::AddA(
+                IntFlags::unsigned_checked(),
+                RegA::A64,
+                Reg32::Reg0,
+                Reg32::Reg1,
+            )),
+            Instr::ControlFlow(ControlFlowOp::Ret),
+        ];
+        let new: Vec<Instr> = vec![
+            Instr::Const(ConstOp::OneA(RegA::A64, Reg32::Reg0)),
+            Instr::Const(ConstOp::ZeroA(RegA::A64, Reg32::Reg2)),
+            Instr::Arithmetic(ArithmeticOp::AddA(
+                IntFlags::unsigned_checked(),
+                RegA::A64,
+                Reg32::Reg0,
+                Reg32::Reg1,
+            )),
+            Instr::ControlFlow(ControlFlowOp::Test),
+            Instr::ControlFlow(ControlFlowOp::Ret),
+        ];
+        let old_lib = Lib::assemble(&old).unwrap();
+        let new_lib = Lib::assemble(&new).unwrap();
+
+        let diff = old_lib.diff::<Instr>(&old_lib).unwrap();
+        assert!(diff.is_empty());
+        assert_eq!(diff.changes().count(), 0);
+
+        let diff = old_lib.diff::<Instr>(&new_lib).unwrap();
+        assert!(!diff.is_empty());
+        assert_eq!(diff.code.len(), 5);
+        assert_eq!(diff.code[1], InstrDiff::Changed {
+            old: 2,
+            new: 2,
+            from: old[1].clone(),
+            to: new[1].clone()
+        });
+        assert!(matches!(diff.code[3], InstrDiff::Inserted {
+            instr: Instr::ControlFlow(ControlFlowOp::Test),
+            ..
+        }));
+        assert_eq!(diff.changes().count(), 2);
+    }
+}
@@ -44,6 +44,7 @@ use crate::isa::{Bytecode, BytecodeError, ExecStep, Instr, InstructionSet};
 use crate::library::segs::IsaSeg;
 use crate::library::{CodeEofError, LibSeg, SegmentError};
 use crate::reg::CoreRegs;
+use crate::vm::Preemption;
 use crate::LIB_NAME_ALUVM;
 
 pub const LIB_ID_TAG: [u8; 32] = *b"urn:ubideco:aluvm:lib:v01#230304";
@@ -89,6 +90,25 @@ impl LibId {
         code: impl AsRef<[u8]>,
         data: impl AsRef<[u8]>,
         libs: &LibSeg,
+    ) -> LibId {
+        LibId::with_raw(
+            isae.as_ref().as_bytes(),
+            code.as_ref(),
+            data.as_ref(),
+            libs.count(),
+            libs.iter().map(|lib| lib.as_slice()),
+        )
+    }
+
+    /// Computes LibId from the raw segment data, where `isae` is a space-separated list of ISA
+    /// extension names and `libs` are ids of the `libs_count` libraries, in their lexicographic
+    /// order.
+    pub(crate) fn with_raw<'a>(
+        isae: &[u8],
+        code: &[u8],
+        data: &[u8],
+        libs_count: u8,
+        libs: impl IntoIterator<Item = &'a [u8]>,
     ) -> LibId {
         let mut tagger = Sha256::default();
         tagger.update(LIB_ID_TAG);
@@ -98,18 +118,15 @@ impl LibId {
         hasher.update(tag);
         hasher.update(tag);
 
-        let isae = isae.as_ref();
-        let code = code.as_ref();
-        let data = data.as_ref();
         hasher.update((isae.len() as u8).to_le_bytes());
-        hasher.update(isae.as_bytes());
+        hasher.update(isae);
         hasher.update((code.len() as u16).to_le_bytes());
         hasher.update(code);
         hasher.update((data.len() as u16).to_le_bytes());
         hasher.update(data);
-        hasher.update([libs.count()]);
+        hasher.update([libs_count]);
         for lib in libs {
-            hasher.update(lib.as_slice());
+            hasher.update(lib);
         }
 
         LibId::from_byte_array(hasher.finalize())
@@ -131,6 +148,43 @@ pub struct Lib {
     pub data: SmallBlob,
     /// Libs segment
     pub libs: LibSeg,
+    /// Provenance metadata, not committed to by the library id
+    #[strict_type(skip)]
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
+    meta: Option<LibMeta>,
+}
+
+/// Provenance metadata of a library, describing how the library was produced.
+///
+/// The metadata are kept outside of the area committed to by the [`LibId`] and are not a part of
+/// the strict-encoded library data, thus adding or stripping them never changes the library id.
+/// They are preserved by the ASCII-armored representation of the library (as armor headers) and
+/// by serde serialization.
+#[derive(Clone, PartialEq, Eq, Hash, Debug, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase")
+)]
+pub struct LibMeta {
+    /// Name of the compiler or assembler which produced the library
+    pub compiler: Option<String>,
+    /// Version of the compiler or assembler
+    pub compiler_version: Option<String>,
+    /// Hash of the source code the library was produced from
+    pub source_hash: Option<String>,
+    /// Build flags used to produce the library
+    pub build_flags: Vec<String>,
+}
+
+impl LibMeta {
+    /// Detects whether the metadata do not contain any information.
+    pub fn is_empty(&self) -> bool {
+        self.compiler.is_none()
+            && self.compiler_version.is_none()
+            && self.source_hash.is_none()
+            && self.build_flags.is_empty()
+    }
 }
 
 impl StrictSerialize for Lib {}
@@ -181,6 +235,10 @@ mod _armor {
 
     const ASCII_ARMOR_ISAE: &str = "ISA-Extensions";
     const ASCII_ARMOR_DEPENDENCY: &str = "Dependency";
+    const ASCII_ARMOR_COMPILER: &str = "Compiler";
+    const ASCII_ARMOR_COMPILER_VERSION: &str = "Compiler-Version";
+    const ASCII_ARMOR_SOURCE_HASH: &str = "Source-Hash";
+    const ASCII_ARMOR_BUILD_FLAG: &str = "Build-Flag";
 
     /// Errors while deserializing library from an ASCII Armor.
     #[derive(Clone, Eq, PartialEq, Debug, Display, Error, From)]
@@ -211,6 +269,20 @@ mod _armor {
             for dep in &self.libs {
                 headers.push(ArmorHeader::new(ASCII_ARMOR_DEPENDENCY, dep.to_string()));
             }
+            if let Some(meta) = &self.meta {
+                if let Some(compiler) = &meta.compiler {
+                    headers.push(ArmorHeader::new(ASCII_ARMOR_COMPILER, compiler.clone()));
+                }
+                if let Some(version) = &meta.compiler_version {
+                    headers.push(ArmorHeader::new(ASCII_ARMOR_COMPILER_VERSION, version.clone()));
+                }
+                if let Some(hash) = &meta.source_hash {
+                    headers.push(ArmorHeader::new(ASCII_ARMOR_SOURCE_HASH, hash.clone()));
+                }
+                for flag in &meta.build_flags {
+                    headers.push(ArmorHeader::new(ASCII_ARMOR_BUILD_FLAG, flag.clone()));
+                }
+            }
             headers
         }
 
@@ -218,10 +290,25 @@ mod _armor {
             self.to_strict_serialized::<U24MAX>().expect("type guarantees").to_vec()
         }
 
-        fn with_headers_data(_headers: Vec<ArmorHeader>, data: Vec<u8>) -> Result<Self, Self::Err> {
+        fn with_headers_data(headers: Vec<ArmorHeader>, data: Vec<u8>) -> Result<Self, Self::Err> {
             // TODO: check id, dependencies and ISAE
             let data = Confined::try_from(data)?;
-            let me = Self::from_strict_serialized::<U24MAX>(data)?;
+            let mut me = Self::from_strict_serialized::<U24MAX>(data)?;
+
+            let mut meta = LibMeta::default();
+            for header in headers {
+                let mut values = header.values.into_iter();
+                match header.title.as_str() {
+                    ASCII_ARMOR_COMPILER => meta.compiler = values.next(),
+                    ASCII_ARMOR_COMPILER_VERSION => meta.compiler_version = values.next(),
+                    ASCII_ARMOR_SOURCE_HASH => meta.source_hash = values.next(),
+                    ASCII_ARMOR_BUILD_FLAG => meta.build_flags.extend(values),
+                    _ => {}
+                }
+            }
+            if !meta.is_empty() {
+                me.meta = Some(meta);
+            }
             Ok(me)
         }
     }
@@ -267,6 +354,7 @@ impl Lib {
             code: SmallBlob::try_from(bytecode)
                 .map_err(|_| SegmentError::CodeSegmentTooLarge(len))?,
             data: SmallBlob::try_from(data).map_err(|_| SegmentError::DataSegmentTooLarge(len))?,
+            meta: None,
         })
     }
 
@@ -288,7 +376,13 @@ impl Lib {
         code_segment.adjust_len(pos);
         let code_segment = SmallBlob::from_collection_unsafe(code_segment.to_vec());
 
-        Ok(Lib { isae: Isa::isa_ids(), libs: libs_segment, code: code_segment, data: data_segment })
+        Ok(Lib {
+            isae: Isa::isa_ids(),
+            libs: libs_segment,
+            code: code_segment,
+            data: data_segment,
+            meta: None,
+        })
     }
 
     /// Disassembles library into a set of instructions
@@ -350,6 +444,20 @@ impl Lib {
     #[inline]
     pub fn libs_segment(&self) -> &LibSeg { &self.libs }
 
+    /// Returns provenance metadata of the library, if any
+    #[inline]
+    pub fn meta(&self) -> Option<&LibMeta> { self.meta.as_ref() }
+
+    /// Attaches provenance metadata to the library, replacing the existing ones. Does not change
+    /// the library id.
+    #[inline]
+    pub fn set_meta(&mut self, meta: LibMeta) -> Option<LibMeta> { self.meta.replace(meta) }
+
+    /// Removes provenance metadata from the library, returning them. Does not change the library
+    /// id.
+    #[inline]
+    pub fn strip_meta(&mut self) -> Option<LibMeta> { self.meta.take() }
+
     /// Executes library code starting at entrypoint
     ///
     /// # Returns
@@ -364,89 +472,155 @@ impl Lib {
     where
         Isa: InstructionSet,
     {
-        #[cfg(feature = "log")]
-        let (m, w, d, g, r, y, z) = (
-            "\x1B[0;35m",
-            "\x1B[1;1m",
-            "\x1B[0;37;2m",
-            "\x1B[0;32m",
-            "\x1B[0;31m",
-            "\x1B[0;33m",
-            "\x1B[0m",
-        );
+        self.exec_inner::<Isa>(entrypoint, registers, context, None)
+    }
 
-        let mut cursor = Cursor::with(&self.code, &self.data, &self.libs);
-        let lib_hash = self.id();
-        cursor.seek(entrypoint).ok()?;
+    /// Executes library code starting at entrypoint, invoking preemption callback each time the
+    /// program execution has spent the amount of fuel specified in `preemption`.
+    ///
+    /// # Returns
+    ///
+    /// Location for the external code jump, if any
+    pub fn exec_preemptible<Isa>(
+        &self,
+        entrypoint: u16,
+        registers: &mut CoreRegs,
+        context: &Isa::Context<'_>,
+        preemption: &mut Preemption,
+    ) -> Option<LibSite>
+    where
+        Isa: InstructionSet,
+    {
+        self.exec_inner::<Isa>(entrypoint, registers, context, Some(preemption))
+    }
 
-        #[cfg(feature = "log")]
-        let mut st0 = registers.st0;
+    pub(crate) fn exec_inner<Isa>(
+        &self,
+        entrypoint: u16,
+        registers: &mut CoreRegs,
+        context: &Isa::Context<'_>,
+        preemption: Option<&mut Preemption>,
+    ) -> Option<LibSite>
+    where
+        Isa: InstructionSet,
+    {
+        let id = self.id();
+        exec_code::<Isa>(
+            &self.code, &self.data, &self.libs, id, entrypoint, registers, context, preemption,
+        )
+    }
+}
 
-        while !cursor.is_eof() {
-            let pos = cursor.pos();
+/// Executes library code given by its segments, starting at entrypoint. Used by all library
+/// representations which keep the code in the form of bytecode.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn exec_code<Isa>(
+    code: &[u8],
+    data: &[u8],
+    libs: &LibSeg,
+    lib_hash: LibId,
+    entrypoint: u16,
+    registers: &mut CoreRegs,
+    context: &Isa::Context<'_>,
+    mut preemption: Option<&mut Preemption>,
+) -> Option<LibSite>
+where
+    Isa: InstructionSet,
+{
+    #[cfg(feature = "log")]
+    let (m, w, d, g, r, y, z) = (
+        "\x1B[0;35m",
+        "\x1B[1;1m",
+        "\x1B[0;37;2m",
+        "\x1B[0;32m",
+        "\x1B[0;31m",
+        "\x1B[0;33m",
+        "\x1B[0m",
+    );
+
+    let mut cursor = Cursor::with(code, data, libs);
+    cursor.seek(entrypoint).ok()?;
+
+    #[cfg(feature = "log")]
+    let mut st0 = registers.st0;
+
+    while !cursor.is_eof() {
+        let pos = cursor.pos();
+
+        #[cfg(not(feature = "log"))]
+        let next = Isa::step(&mut cursor, registers, LibSite::with(pos, lib_hash), context)?;
 
+        #[cfg(feature = "log")]
+        let next = {
             let instr = Isa::decode(&mut cursor).ok()?;
 
-            #[cfg(feature = "log")]
-            {
-                eprint!("{m}@{pos:06}:{z} {: <32}; ", instr.to_string());
-                for reg in instr.src_regs() {
-                    let val = registers.get(reg);
-                    eprint!("{d}{reg}={z}{w}{val}{z} ");
-                }
+            eprint!("{m}@{pos:06}:{z} {: <32}; ", instr.to_string());
+            for reg in instr.src_regs() {
+                let val = registers.get(reg);
+                eprint!("{d}{reg}={z}{w}{val}{z} ");
             }
 
-            let next = instr.exec(registers, LibSite::with(pos, lib_hash), context);
+            if !registers.check_src_regs(&instr) {
+                eprintln!("{r}read of uninitialized register{z}");
+                return None;
+            }
 
-            #[cfg(feature = "log")]
-            {
-                eprint!("-> ");
-                for reg in instr.dst_regs() {
-                    let val = registers.get(reg);
-                    eprint!("{g}{reg}={y}{val}{z} ");
-                }
-                if st0 != registers.st0 {
-                    let c = if registers.st0 { g } else { r };
-                    eprint!(" {d}st0={z}{c}{}{z} ", registers.st0);
-                }
+            let next = instr.exec(registers, LibSite::with(pos, lib_hash), context);
 
-                st0 = registers.st0;
+            eprint!("-> ");
+            for reg in instr.dst_regs() {
+                let val = registers.get(reg);
+                eprint!("{g}{reg}={y}{val}{z} ");
+            }
+            if st0 != registers.st0 {
+                let c = if registers.st0 { g } else { r };
+                eprint!(" {d}st0={z}{c}{}{z} ", registers.st0);
             }
+            st0 = registers.st0;
 
             if !registers.acc_complexity(instr) {
-                #[cfg(feature = "log")]
                 eprintln!("complexity overflow");
                 return None;
             }
-            match next {
-                ExecStep::Stop => {
-                    #[cfg(feature = "log")]
-                    {
-                        let c = if registers.st0 { g } else { r };
-                        eprintln!("execution stopped; {d}st0={z}{c}{}{z}", registers.st0);
-                    }
-                    return None;
-                }
-                ExecStep::Next => {
-                    #[cfg(feature = "log")]
-                    eprintln!();
-                    continue;
-                }
-                ExecStep::Jump(pos) => {
-                    #[cfg(feature = "log")]
-                    eprintln!("{}", pos);
-                    cursor.seek(pos).ok()?;
-                }
-                ExecStep::Call(site) => {
-                    #[cfg(feature = "log")]
-                    eprintln!("{}", site);
-                    return Some(site);
+            next
+        };
+
+        if let Some(preemption) = preemption.as_deref_mut() {
+            if !preemption.check(registers) {
+                registers.st0 = false;
+                #[cfg(feature = "log")]
+                eprintln!("halted by preemption callback");
+                return None;
+            }
+        }
+        match next {
+            ExecStep::Stop => {
+                #[cfg(feature = "log")]
+                {
+                    let c = if registers.st0 { g } else { r };
+                    eprintln!("execution stopped; {d}st0={z}{c}{}{z}", registers.st0);
                 }
+                return None;
+            }
+            ExecStep::Next => {
+                #[cfg(feature = "log")]
+                eprintln!();
+                continue;
+            }
+            ExecStep::Jump(pos) => {
+                #[cfg(feature = "log")]
+                eprintln!("{}", pos);
+                cursor.seek(pos).ok()?;
+            }
+            ExecStep::Call(site) => {
+                #[cfg(feature = "log")]
+                eprintln!("{}", site);
+                return Some(site);
             }
         }
-
-        None
     }
+
+    None
 }
 
 /// Location within a library
@@ -467,7 +641,7 @@ pub struct LibSite {
 impl LibSite {
     /// Constricts library site reference from a given position and library hash
     /// value
-    pub fn with(pos: u16, lib: LibId) -> LibSite { LibSite { lib, pos } }
+    pub const fn with(pos: u16, lib: LibId) -> LibSite { LibSite { lib, pos } }
 }
 
 #[cfg(test)]
@@ -508,4 +682,69 @@ mod test {
 
         assert_eq!(id, LibId::from_str("650XHPmhWpXWR5RUz4B5jXjeDqcyrHXpdZxYaX9gfO4").unwrap());
     }
+
+    #[test]
+    fn nop_disassemble() {
+        use crate::isa::{ControlFlowOp, Instr};
+
+        let lib = Lib::with("ALU", vec![0xFF, 0x07], vec![], none!()).unwrap();
+        assert_eq!(lib.disassemble::<Instr>().unwrap(), [
+            Instr::Nop,
+            Instr::ControlFlow(ControlFlowOp::Ret)
+        ]);
+    }
+
+    #[test]
+    fn meta_not_committed() {
+        let mut lib = Lib::with("ALU", vec![0x00], vec![], none!()).unwrap();
+        let id = lib.id();
+        let meta = LibMeta {
+            compiler: Some(String::from("aluasm")),
+            compiler_version: Some(String::from("0.11.0")),
+            source_hash: None,
+            build_flags: vec![String::from("--release")],
+        };
+        assert_eq!(lib.set_meta(meta.clone()), None);
+        assert_eq!(lib.meta(), Some(&meta));
+        assert_eq!(lib.id(), id);
+        assert_eq!(lib.strip_meta(), Some(meta));
+        assert_eq!(lib.meta(), None);
+        assert_eq!(lib.id(), id);
+    }
+
+    #[test]
+    #[cfg(feature = "ascii-armor")]
+    fn meta_armor_roundtrip() {
+        use armor::AsciiArmor;
+
+        let mut lib = Lib::with("ALU", vec![0x00], vec![0xDE, 0xAD], none!()).unwrap();
+        let id = lib.id();
+        let plain = lib.to_ascii_armored_string();
+        let meta = LibMeta {
+            compiler: Some(String::from("aluasm")),
+            compiler_version: Some(String::from("0.11.0")),
+            source_hash: Some(String::from(
+                "8f434346648f6b96df89dda901c5176b10a6d83961dd3c1ac88b59b2dc327aa4",
+            )),
+            build_flags: vec![String::from("--release"), String::from("--no-std")],
+        };
+        lib.set_meta(meta.clone());
+
+        let armored = lib.to_ascii_armored_string();
+        assert!(armored.contains("Compiler: aluasm\n"));
+        assert!(armored.contains("Compiler-Version: 0.11.0\n"));
+        assert!(armored.contains(&format!("Source-Hash: {}\n", meta.source_hash.as_ref().unwrap())));
+        assert!(armored.contains("Build-Flag: --release\n"));
+        assert!(armored.contains("Build-Flag: --no-std\n"));
+        assert!(armored.contains(&format!("Id: {id}\n")));
+
+        let restored = Lib::from_ascii_armored_str(&armored).unwrap();
+        assert_eq!(restored.meta(), Some(&meta));
+        assert_eq!(restored.id(), id);
+        assert_eq!(restored, lib);
+
+        let restored = Lib::from_ascii_armored_str(&plain).unwrap();
+        assert_eq!(restored.meta(), None);
+        assert_eq!(restored.id(), id);
+    }
 }
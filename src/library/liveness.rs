@@ -0,0 +1,277 @@
+// Reference rust implementation of AluVM (arithmetic logic unit virtual machine).
+// To find more on AluVM please check <https://aluvm.org>
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2021-2024 by
+//     Dr Maxim Orlovsky <orlovsky@ubideco.org>
+//
+// Copyright (C) 2021-2022 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2023-2024 UBIDECO Institute. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Register liveness and pressure analysis of library code.
+
+#[cfg(all(feature = "alloc", not(feature = "std")))]
+use alloc::collections::{BTreeMap, BTreeSet};
+#[cfg(all(feature = "alloc", not(feature = "std")))]
+use alloc::vec::Vec;
+use core::ops::Range;
+#[cfg(feature = "std")]
+use std::collections::{BTreeMap, BTreeSet};
+
+use super::{CodeEofError, Cursor, Lib, Read};
+use crate::isa::{Bytecode, ControlFlowOp, Instr, InstructionSet};
+use crate::reg::{Reg, RegBlock};
+
+/// Liveness of registers at a single instruction
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct InstrLiveness {
+    /// Offset of the instruction in the code segment
+    pub pos: u16,
+    /// Registers which values are used by the instruction or by the code executed after it
+    pub live_in: BTreeSet<Reg>,
+    /// Registers which values are used by the code executed after the instruction
+    pub live_out: BTreeSet<Reg>,
+    /// Registers written by the instruction
+    pub defs: BTreeSet<Reg>,
+}
+
+impl InstrLiveness {
+    /// Iterates over registers which must be allocated during the instruction execution: the live
+    /// registers and registers written by the instruction.
+    pub fn occupied(&self) -> impl Iterator<Item = Reg> + '_ {
+        self.live_in.union(&self.defs).copied()
+    }
+
+    /// Returns number of registers from a given block which must be allocated during the
+    /// instruction execution.
+    pub fn pressure(&self, block: RegBlock) -> usize {
+        self.occupied().filter(|reg| reg.family() == block).count()
+    }
+}
+
+/// Register liveness analysis of library code.
+///
+/// The analysis is a backward data-flow over the control-flow graph of the library, which is
+/// built from the jumps and routine calls within the library. The analysis is conservative in the
+/// following:
+/// - calls to other libraries are treated as not using any registers, and no register is considered
+///   live after the program completion (i.e. values returned to the host are not accounted for);
+/// - each `ret` may return to any of the routine call sites of the library;
+/// - execution paths end only with `fail`, `exec` and with `ret` in a library having no routine
+///   calls;
+/// - jumps to offsets which are not instruction boundaries are ignored.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct Liveness {
+    instrs: Vec<InstrLiveness>,
+    code_len: u16,
+}
+
+impl Liveness {
+    /// Analyzes liveness of registers in the library code.
+    ///
+    /// # Errors
+    ///
+    /// If the library code can't be decoded.
+    pub fn analyze<Extension>(lib: &Lib) -> Result<Self, CodeEofError>
+    where
+        Extension: InstructionSet,
+    {
+        let mut code = Vec::new();
+        let mut cursor = Cursor::with(&lib.code, &lib.data, &lib.libs);
+        while !cursor.is_eof() {
+            let pos = cursor.pos();
+            code.push((pos, Instr::<Extension>::decode(&mut cursor)?));
+        }
+        let index =
+            code.iter().enumerate().map(|(no, (pos, _))| (*pos, no)).collect::<BTreeMap<_, _>>();
+
+        let next = |no: usize| Some(no + 1).filter(|next| *next < code.len());
+        let returns = code
+            .iter()
+            .enumerate()
+            .filter(|(_, (_, instr))| {
+                matches!(instr, Instr::ControlFlow(ControlFlowOp::Routine(_)))
+            })
+            .filter_map(|(no, _)| next(no))
+            .collect::<Vec<_>>();
+        let succs = code
+            .iter()
+            .enumerate()
+            .map(|(no, (_, instr))| match instr {
+                Instr::ControlFlow(ControlFlowOp::Fail | ControlFlowOp::Exec(_)) => vec![],
+                Instr::ControlFlow(ControlFlowOp::Jmp(pos)) => {
+                    index.get(pos).copied().into_iter().collect()
+                }
+                Instr::ControlFlow(ControlFlowOp::Jif(pos) | ControlFlowOp::Routine(pos)) => {
+                    index.get(pos).copied().into_iter().chain(next(no)).collect()
+                }
+                Instr::ControlFlow(ControlFlowOp::Ret) => returns.clone(),
+                _ => next(no).into_iter().collect(),
+            })
+            .collect::<Vec<Vec<usize>>>();
+
+        let mut instrs = code
+            .iter()
+            .map(|(pos, instr)| InstrLiveness {
+                pos: *pos,
+                live_in: instr.src_regs(),
+                live_out: BTreeSet::new(),
+                defs: instr.dst_regs(),
+            })
+            .collect::<Vec<_>>();
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for no in (0..instrs.len()).rev() {
+                let live_out = succs[no]
+                    .iter()
+                    .flat_map(|succ| instrs[*succ].live_in.iter().copied())
+                    .collect::<BTreeSet<_>>();
+                let instr = &mut instrs[no];
+                let len = instr.live_in.len();
+                instr.live_in.extend(live_out.difference(&instr.defs).copied());
+                changed |= instr.live_in.len() != len;
+                instr.live_out = live_out;
+            }
+        }
+
+        Ok(Liveness { instrs, code_len: lib.code.len() as u16 })
+    }
+
+    /// Returns liveness information for each of the library instructions, in the order of their
+    /// offsets.
+    #[inline]
+    pub fn instrs(&self) -> &[InstrLiveness] { &self.instrs }
+
+    /// Returns liveness information for the instruction at a given offset, if the offset is an
+    /// instruction boundary.
+    pub fn at(&self, pos: u16) -> Option<&InstrLiveness> {
+        self.instrs.binary_search_by_key(&pos, |instr| instr.pos).ok().map(|no| &self.instrs[no])
+    }
+
+    /// Returns live ranges of a register, as code segment offset ranges covering instructions
+    /// during which the register must be allocated.
+    pub fn live_ranges(&self, reg: Reg) -> Vec<Range<u16>> {
+        let mut ranges: Vec<Range<u16>> = vec![];
+        for (no, instr) in self.instrs.iter().enumerate() {
+            if !instr.live_in.contains(&reg) && !instr.defs.contains(&reg) {
+                continue;
+            }
+            let end = self.instrs.get(no + 1).map(|next| next.pos).unwrap_or(self.code_len);
+            match ranges.last_mut() {
+                Some(range) if range.end == instr.pos => range.end = end,
+                _ => ranges.push(instr.pos..end),
+            }
+        }
+        ranges
+    }
+
+    /// Returns maximal number of simultaneously allocated registers from a given block.
+    pub fn max_pressure(&self, block: RegBlock) -> usize {
+        self.instrs.iter().map(|instr| instr.pressure(block)).max().unwrap_or_default()
+    }
+
+    /// Returns the first instruction at which the register pressure for a given block reaches its
+    /// maximum, or `None` if the library doesn't use registers from the block.
+    pub fn pressure_peak(&self, block: RegBlock) -> Option<&InstrLiveness> {
+        let max = self.max_pressure(block);
+        self.instrs.iter().find(|instr| max > 0 && instr.pressure(block) == max)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::data::{MaybeNumber, Number};
+    use crate::isa::{ArithmeticOp, IntFlags, PutOp, ReservedOp};
+    use crate::reg::{Reg32, RegA};
+
+    #[test]
+    fn loop_liveness() {
+        let put = |idx, val: u8| {
+            Instr::Put(PutOp::PutA(RegA::A8, idx, Box::new(MaybeNumber::from(Number::from(val)))))
+        };
+        let lib = Lib::assemble::<Instr<ReservedOp>>(&[
+            put(Reg32::Reg0, 1),
+            put(Reg32::Reg1, 2),
+            Instr::Arithmetic(ArithmeticOp::AddA(
+                IntFlags { signed: false, wrap: true },
+                RegA::A8,
+                Reg32::Reg0,
+                Reg32::Reg1,
+            )),
+            Instr::ControlFlow(ControlFlowOp::Jif(4)),
+            Instr::ControlFlow(ControlFlowOp::Ret),
+        ])
+        .unwrap();
+
+        let liveness = Liveness::analyze::<ReservedOp>(&lib).unwrap();
+        let pos = liveness.instrs().iter().map(|instr| instr.pos).collect::<Vec<_>>();
+        assert_eq!(pos[1], 4);
+        assert_eq!(liveness.live_ranges(Reg::A(RegA::A8, Reg32::Reg0)), vec![0..pos[4]]);
+        assert_eq!(liveness.live_ranges(Reg::A(RegA::A8, Reg32::Reg1)), vec![4..pos[3]]);
+        assert!(liveness.at(pos[4]).unwrap().live_in.is_empty());
+        assert_eq!(liveness.max_pressure(RegBlock::A), 2);
+        assert_eq!(liveness.pressure_peak(RegBlock::A).unwrap().pos, 4);
+        assert_eq!(liveness.max_pressure(RegBlock::S), 0);
+        assert_eq!(liveness.pressure_peak(RegBlock::S), None);
+    }
+
+    #[test]
+    fn routine_liveness() {
+        let put = |idx, val: u8| {
+            Instr::Put(PutOp::PutA(RegA::A8, idx, Box::new(MaybeNumber::from(Number::from(val)))))
+        };
+        let add = |idx| {
+            Instr::Arithmetic(ArithmeticOp::AddA(
+                IntFlags { signed: false, wrap: true },
+                RegA::A8,
+                idx,
+                idx,
+            ))
+        };
+        let code = |routine| {
+            vec![
+                put(Reg32::Reg0, 1),
+                put(Reg32::Reg1, 2),
+                Instr::ControlFlow(ControlFlowOp::Routine(routine)),
+                add(Reg32::Reg1),
+                Instr::ControlFlow(ControlFlowOp::Fail),
+                add(Reg32::Reg0),
+                Instr::ControlFlow(ControlFlowOp::Ret),
+            ]
+        };
+        let lib = Lib::assemble::<Instr<ReservedOp>>(&code(0)).unwrap();
+        let liveness = Liveness::analyze::<ReservedOp>(&lib).unwrap();
+        let pos = liveness.instrs().iter().map(|instr| instr.pos).collect::<Vec<_>>();
+        let lib = Lib::assemble::<Instr<ReservedOp>>(&code(pos[5])).unwrap();
+        let liveness = Liveness::analyze::<ReservedOp>(&lib).unwrap();
+
+        // `ret` returns after the routine call, where `a8[1]` is used
+        let ret = liveness.at(pos[6]).unwrap();
+        assert_eq!(ret.live_out, bset![Reg::A(RegA::A8, Reg32::Reg1)]);
+        assert_eq!(liveness.live_ranges(Reg::A(RegA::A8, Reg32::Reg1)), vec![
+            pos[1]..pos[4],
+            pos[5]..lib.code.len() as u16
+        ]);
+        // `fail` ends the execution path
+        assert!(liveness.at(pos[4]).unwrap().live_in.is_empty());
+        assert_eq!(liveness.live_ranges(Reg::A(RegA::A8, Reg32::Reg0)), vec![
+            pos[0]..pos[3],
+            pos[5]..pos[6]
+        ]);
+    }
+}
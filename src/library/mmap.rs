@@ -0,0 +1,323 @@
+// Reference rust implementation of AluVM (arithmetic logic unit virtual machine).
+// To find more on AluVM please check <https://aluvm.org>
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2021-2024 by
+//     Dr Maxim Orlovsky <orlovsky@ubideco.org>
+//
+// Copyright (C) 2021-2022 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2023-2024 UBIDECO Institute. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Zero-copy access to libraries stored in memory-mapped files.
+
+use std::ops::Range;
+use std::sync::{Arc, OnceLock};
+
+use amplify::confinement::{Confined, U24 as U24MAX};
+use memmap2::Mmap;
+use strict_encoding::{DeserializeError, StrictDecode, StrictDeserialize, StrictReader};
+
+use super::lib::exec_code;
+use crate::isa::InstructionSet;
+use crate::library::{Lib, LibId, LibSeg, LibSite};
+use crate::reg::CoreRegs;
+use crate::vm::{ExecLib, Preemption};
+
+/// Errors accessing libraries stored in memory-mapped files
+#[derive(Clone, PartialEq, Eq, Debug, Display)]
+#[display(doc_comments)]
+pub enum MmapError {
+    /// library data at offset {0} are truncated.
+    Truncated(usize),
+
+    /// library at offset {0} is invalid. Details: {1}
+    Invalid(usize, DeserializeError),
+}
+
+impl ::std::error::Error for MmapError {}
+
+/// Library located in a memory-mapped file.
+///
+/// Constructing the library only scans the boundaries of its segments and decodes the list of
+/// the libraries it depends on. The code and data segments are never copied: they are accessed
+/// and executed (see [`ExecLib`](crate::ExecLib)) directly from the mapped memory. Full
+/// deserialization, which validates all the library data, happens on the first call to
+/// [`MappedLib::lib`] and is not required for the execution.
+#[derive(Debug)]
+pub struct MappedLib {
+    map: Arc<Mmap>,
+    offset: usize,
+    isae: Vec<Range<usize>>,
+    code: Range<usize>,
+    data: Range<usize>,
+    libs: Range<usize>,
+    deps: LibSeg,
+    lib: OnceLock<Result<Lib, MmapError>>,
+}
+
+impl Lib {
+    /// Locates strict-serialized library at `offset` within the memory-mapped file, without
+    /// copying or deserializing it.
+    ///
+    /// # Errors
+    ///
+    /// If the library data are truncated or its libs segment is invalid. Other data
+    /// inconsistencies are detected only when the library is deserialized with
+    /// [`MappedLib::lib`].
+    pub fn from_mmap(map: Arc<Mmap>, offset: usize) -> Result<MappedLib, MmapError> {
+        MappedLib::with(map, offset)
+    }
+}
+
+impl MappedLib {
+    fn with(map: Arc<Mmap>, offset: usize) -> Result<Self, MmapError> {
+        let mut scanner = Scanner { bytes: &map[..], pos: offset, offset };
+        let isae = (0..scanner.u8()?)
+            .map(|_| {
+                let len = scanner.u8()? as usize;
+                scanner.take(len)
+            })
+            .collect::<Result<_, _>>()?;
+        let len = scanner.u16()? as usize;
+        let code = scanner.take(len)?;
+        let len = scanner.u16()? as usize;
+        let data = scanner.take(len)?;
+        let start = scanner.pos;
+        let count = scanner.u8()? as usize;
+        let libs = scanner.take(count * 32)?;
+        let mut reader = StrictReader::in_memory::<U24MAX>(&map[start..libs.end]);
+        let deps = LibSeg::strict_decode(&mut reader)
+            .map_err(|err| MmapError::Invalid(offset, err.into()))?;
+        Ok(MappedLib { map, offset, isae, code, data, libs, deps, lib: OnceLock::new() })
+    }
+
+    /// Locates all libraries in a memory-mapped library store, which consists of a sequence of
+    /// strict-serialized libraries.
+    pub fn scan(map: Arc<Mmap>) -> Result<Vec<MappedLib>, MmapError> {
+        let mut libs = vec![];
+        let mut offset = 0;
+        while offset < map.len() {
+            let lib = MappedLib::with(map.clone(), offset)?;
+            offset = lib.end();
+            libs.push(lib);
+        }
+        Ok(libs)
+    }
+
+    /// Returns offset of the library data in the mapped file.
+    #[inline]
+    pub fn offset(&self) -> usize { self.offset }
+
+    /// Returns offset of the first byte following the library data in the mapped file.
+    #[inline]
+    pub fn end(&self) -> usize { self.libs.end }
+
+    /// Returns code segment of the library.
+    #[inline]
+    pub fn code(&self) -> &[u8] { &self.map[self.code.clone()] }
+
+    /// Returns data segment of the library.
+    #[inline]
+    pub fn data(&self) -> &[u8] { &self.map[self.data.clone()] }
+
+    /// Returns libraries segment of the library.
+    #[inline]
+    pub fn libs_segment(&self) -> &LibSeg { &self.deps }
+
+    /// Computes id of the library directly from the mapped data, without deserializing it.
+    pub fn id(&self) -> LibId {
+        let mut isae = Vec::with_capacity(self.isae.iter().map(|r| r.len() + 1).sum());
+        for (no, name) in self.isae.iter().enumerate() {
+            if no > 0 {
+                isae.push(b' ');
+            }
+            isae.extend_from_slice(&self.map[name.clone()]);
+        }
+        let libs = &self.map[self.libs.clone()];
+        LibId::with_raw(&isae, self.code(), self.data(), (libs.len() / 32) as u8, libs.chunks(32))
+    }
+
+    /// Returns library deserialized from the mapped data. The library is deserialized and
+    /// validated on the first call; subsequent calls return the cached value.
+    pub fn lib(&self) -> Result<&Lib, MmapError> {
+        self.lib
+            .get_or_init(|| {
+                let data = Confined::try_from(self.map[self.offset..self.end()].to_vec())
+                    .map_err(|_| MmapError::Truncated(self.offset))?;
+                Lib::from_strict_serialized::<U24MAX>(data)
+                    .map_err(|err| MmapError::Invalid(self.offset, err))
+            })
+            .as_ref()
+            .map_err(MmapError::clone)
+    }
+}
+
+impl<Isa> ExecLib<Isa> for MappedLib
+where
+    Isa: InstructionSet,
+{
+    #[inline]
+    fn exec_lib(
+        &self,
+        entrypoint: u16,
+        registers: &mut CoreRegs,
+        context: &Isa::Context<'_>,
+        preemption: Option<&mut Preemption>,
+    ) -> Option<LibSite> {
+        exec_code::<Isa>(
+            self.code(),
+            self.data(),
+            self.libs_segment(),
+            self.id(),
+            entrypoint,
+            registers,
+            context,
+            preemption,
+        )
+    }
+}
+
+struct Scanner<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+    offset: usize,
+}
+
+impl<'a> Scanner<'a> {
+    fn take(&mut self, len: usize) -> Result<Range<usize>, MmapError> {
+        let start = self.pos;
+        let end = start
+            .checked_add(len)
+            .filter(|end| *end <= self.bytes.len())
+            .ok_or(MmapError::Truncated(self.offset))?;
+        self.pos = end;
+        Ok(start..end)
+    }
+
+    fn u8(&mut self) -> Result<u8, MmapError> {
+        let range = self.take(1)?;
+        Ok(self.bytes[range.start])
+    }
+
+    fn u16(&mut self) -> Result<u16, MmapError> {
+        let range = self.take(2)?;
+        Ok(u16::from_le_bytes([self.bytes[range.start], self.bytes[range.start + 1]]))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use memmap2::MmapMut;
+    use strict_encoding::StrictSerialize;
+
+    use super::*;
+    use crate::data::{MaybeNumber, Number};
+    use crate::isa::{ControlFlowOp, Instr, PutOp};
+    use crate::reg::{Reg32, RegA};
+    use crate::Vm;
+
+    fn map_store(libs: &[&Lib]) -> Arc<Mmap> {
+        let mut store = vec![];
+        for lib in libs {
+            store.extend(lib.to_strict_serialized::<U24MAX>().unwrap().to_vec());
+        }
+        let mut map = MmapMut::map_anon(store.len()).unwrap();
+        map.copy_from_slice(&store);
+        Arc::new(map.make_read_only().unwrap())
+    }
+
+    #[test]
+    fn mapped_exec() {
+        let put = |idx, val: u64| -> Instr {
+            Instr::Put(PutOp::PutA(RegA::A64, idx, Box::new(MaybeNumber::from(Number::from(val)))))
+        };
+        let callee =
+            Lib::assemble(&[put(Reg32::Reg2, 0xDEAD), Instr::ControlFlow(ControlFlowOp::Ret)])
+                .unwrap();
+        let caller = Lib::assemble(&[
+            put(Reg32::Reg1, 7),
+            Instr::ControlFlow(ControlFlowOp::Call(LibSite::with(0, callee.id()))),
+            Instr::ControlFlow(ControlFlowOp::Ret),
+        ])
+        .unwrap();
+        let map = map_store(&[&caller, &callee]);
+        let mapped = MappedLib::scan(map).unwrap();
+        assert_eq!(mapped[0].libs_segment(), &caller.libs);
+
+        let entry = LibSite::with(0, caller.id());
+        let mut vm = Vm::<Instr>::new();
+        assert!(vm.exec(
+            entry,
+            |id| [&caller, &callee].iter().copied().find(|lib| lib.id() == id),
+            &()
+        ));
+        let mut mapped_vm = Vm::<Instr>::new();
+        assert!(mapped_vm.exec_with(
+            entry,
+            |id| mapped.iter().find(|lib| lib.id() == id),
+            &(),
+            None
+        ));
+        assert_eq!(mapped_vm.registers.get_n(RegA::A64, Reg32::Reg1), Number::from(7u64).into());
+        assert_eq!(
+            mapped_vm.registers.get_n(RegA::A64, Reg32::Reg2),
+            Number::from(0xDEADu64).into()
+        );
+        assert_eq!(mapped_vm.registers.instr_count(), vm.registers.instr_count());
+        // Execution doesn't need the library to be deserialized
+        assert!(mapped.iter().all(|lib| lib.lib.get().is_none()));
+    }
+
+    #[test]
+    fn mapped_store() {
+        let lib1 = Lib::with("ALU BPDIGEST", vec![0x00, 0x01], vec![0xA5; 16], none!()).unwrap();
+        let lib2 = Lib::with("ALU", vec![0x02], vec![], none!()).unwrap();
+        let map = map_store(&[&lib1, &lib2]);
+        let store = map.to_vec();
+
+        let libs = MappedLib::scan(map).unwrap();
+        assert_eq!(libs.len(), 2);
+        assert_eq!(libs[0].code(), &[0x00, 0x01]);
+        assert_eq!(libs[0].data(), &[0xA5; 16]);
+        assert_eq!(libs[0].id(), lib1.id());
+        assert_eq!(libs[1].id(), lib2.id());
+        assert_eq!(libs[1].lib().unwrap(), &lib2);
+        assert_eq!(libs[1].end(), store.len());
+
+        let truncated = Arc::new({
+            let mut map = MmapMut::map_anon(store.len() - 1).unwrap();
+            map.copy_from_slice(&store[..store.len() - 1]);
+            map.make_read_only().unwrap()
+        });
+        assert_eq!(MappedLib::scan(truncated).unwrap_err(), MmapError::Truncated(libs[1].offset()));
+
+        // Libs segment must be ordered in the same way as required by the deserialization
+        let lib3 = Lib::with(
+            "ALU",
+            vec![0x02],
+            vec![],
+            LibSeg::try_from_iter([lib1.id(), lib2.id()]).unwrap(),
+        )
+        .unwrap();
+        let mut store = lib3.to_strict_serialized::<U24MAX>().unwrap().to_vec();
+        let len = store.len();
+        store[len - 64..].rotate_left(32);
+        let mut map = MmapMut::map_anon(store.len()).unwrap();
+        map.copy_from_slice(&store);
+        let map = Arc::new(map.make_read_only().unwrap());
+        assert!(matches!(MappedLib::scan(map).unwrap_err(), MmapError::Invalid(0, _)));
+    }
+}
@@ -23,15 +23,37 @@
 
 //! Business logic and data structures for working with AluVM code libraries
 
+#[cfg(feature = "cache")]
+mod cache;
 pub mod constants;
 mod cursor;
+mod decoded;
+mod diff;
 mod lib;
+mod liveness;
+#[cfg(feature = "mmap")]
+mod mmap;
+#[cfg(feature = "std")]
+mod pool;
+#[cfg(feature = "async")]
+mod registry;
 mod rw;
 mod segs;
 
+#[cfg(feature = "cache")]
+pub use cache::{CacheMetrics, LibCache, LIB_CACHE_DEFAULT_CAPACITY, LIB_CACHE_SHARDS};
 pub use cursor::Cursor;
+pub use decoded::{DecodedLib, Fusion};
+pub use diff::{DataDiff, InstrDiff, LibDiff};
 #[cfg(feature = "ascii-armor")]
 pub use lib::LibArmorError;
-pub use lib::{AssemblerError, Lib, LibId, LibSite};
+pub use lib::{AssemblerError, Lib, LibId, LibMeta, LibSite};
+pub use liveness::{InstrLiveness, Liveness};
+#[cfg(feature = "mmap")]
+pub use mmap::{MappedLib, MmapError};
+#[cfg(feature = "std")]
+pub use pool::{ConstPool, PooledLib, CONST_POOL_MIN_LEN};
+#[cfg(feature = "async")]
+pub use registry::{verify_lib, LibRegistry, RegistryError, RegistryFuture};
 pub use rw::{CodeEofError, Read, Write, WriteError};
 pub use segs::{IsaName, IsaSeg, IsaSegError, LibSeg, SegmentError};
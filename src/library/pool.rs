@@ -0,0 +1,283 @@
+// Reference rust implementation of AluVM (arithmetic logic unit virtual machine).
+// To find more on AluVM please check <https://aluvm.org>
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2021-2024 by
+//     Dr Maxim Orlovsky <orlovsky@ubideco.org>
+//
+// Copyright (C) 2021-2022 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2023-2024 UBIDECO Institute. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Program-level pool of data segment constants shared by multiple libraries.
+
+use std::collections::HashMap;
+use std::ops::Range;
+use std::sync::Arc;
+
+use amplify::confinement::SmallBlob;
+
+use crate::isa::{Bytecode, Instr, InstructionSet};
+use crate::library::{Cursor, IsaSeg, Lib, LibId, LibMeta, LibSeg, Read};
+
+/// Default minimal length of a constant stored in [`ConstPool`]; shorter constants are kept
+/// within the library.
+pub const CONST_POOL_MIN_LEN: usize = 32;
+
+/// Pool of constants from data segments of multiple libraries.
+///
+/// Identical constants referenced by different libraries (like curve generator points or
+/// standard tags) are stored in the pool once. Libraries added to the pool keep the code segment
+/// and refer to the pooled constants by index ([`PooledLib`]); the original library, with the same
+/// id, can be reconstructed with [`ConstPool::materialize`].
+#[derive(Clone, Debug)]
+pub struct ConstPool {
+    consts: Vec<Arc<[u8]>>,
+    index: HashMap<Arc<[u8]>, usize>,
+    min_len: usize,
+}
+
+impl Default for ConstPool {
+    fn default() -> Self { ConstPool::with_min_len(CONST_POOL_MIN_LEN) }
+}
+
+/// Part of a library data segment
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+enum Chunk {
+    /// Constant stored in the pool under a given index
+    Pooled(usize),
+    /// Data kept within the library
+    Inline(Vec<u8>),
+}
+
+/// Library with its data segment constants stored in a [`ConstPool`].
+///
+/// Pooled library is a storage representation only and can't be executed directly: it has to be
+/// turned back into [`Lib`] with [`ConstPool::materialize`] before being provided to the VM (for
+/// instance, by the library resolver, which may keep materialized libraries in a cache).
+#[derive(Clone, Debug)]
+pub struct PooledLib {
+    id: LibId,
+    isae: IsaSeg,
+    code: SmallBlob,
+    libs: LibSeg,
+    meta: Option<LibMeta>,
+    chunks: Vec<Chunk>,
+}
+
+impl PooledLib {
+    /// Returns id of the library
+    #[inline]
+    pub fn id(&self) -> LibId { self.id }
+
+    /// Returns indexes of the pooled constants referenced by the library
+    pub fn pooled(&self) -> impl Iterator<Item = usize> + '_ {
+        self.chunks.iter().filter_map(|chunk| match chunk {
+            Chunk::Pooled(idx) => Some(*idx),
+            Chunk::Inline(_) => None,
+        })
+    }
+}
+
+impl ConstPool {
+    /// Constructs pool storing constants of at least `min_len` bytes.
+    pub fn with_min_len(min_len: usize) -> Self {
+        ConstPool { consts: vec![], index: HashMap::new(), min_len: min_len.max(1) }
+    }
+
+    /// Returns number of constants in the pool
+    #[inline]
+    pub fn len(&self) -> usize { self.consts.len() }
+
+    /// Detects whether the pool is empty
+    #[inline]
+    pub fn is_empty(&self) -> bool { self.consts.is_empty() }
+
+    /// Returns total size of the pooled constants, in bytes
+    pub fn size(&self) -> usize { self.consts.iter().map(|c| c.len()).sum() }
+
+    /// Returns constant with a given index
+    #[inline]
+    pub fn get(&self, idx: usize) -> Option<&[u8]> { self.consts.get(idx).map(AsRef::as_ref) }
+
+    /// Adds constant to the pool, returning its index. If the pool already contains the same
+    /// constant, returns index of the existing one.
+    pub fn intern(&mut self, bytes: &[u8]) -> usize {
+        if let Some(idx) = self.index.get(bytes) {
+            return *idx;
+        }
+        let bytes = Arc::<[u8]>::from(bytes);
+        let idx = self.consts.len();
+        self.consts.push(bytes.clone());
+        self.index.insert(bytes, idx);
+        idx
+    }
+
+    /// Moves constants referenced by the library code from its data segment into the pool.
+    ///
+    /// Constants are detected by decoding the library code; if the code can't be decoded till its
+    /// end, constants referenced by the undecodable part are kept within the library.
+    pub fn add<Extension>(&mut self, lib: &Lib) -> PooledLib
+    where
+        Extension: InstructionSet,
+    {
+        let data = lib.data.as_slice();
+        let mut ranges = data_refs::<Extension>(lib)
+            .into_iter()
+            .map(|range| range.start.min(data.len())..range.end.min(data.len()))
+            .filter(|range| range.len() >= self.min_len)
+            .collect::<Vec<_>>();
+        ranges.sort_by_key(|range| range.start);
+
+        let mut chunks = vec![];
+        let mut pos = 0;
+        let mut merged: Option<Range<usize>> = None;
+        for range in ranges {
+            match merged {
+                Some(ref mut last) if range.start < last.end => {
+                    last.end = last.end.max(range.end);
+                    continue;
+                }
+                Some(last) => {
+                    pos = self.push_chunks(&mut chunks, data, pos, last);
+                }
+                None => {}
+            }
+            merged = Some(range);
+        }
+        if let Some(last) = merged {
+            pos = self.push_chunks(&mut chunks, data, pos, last);
+        }
+        if pos < data.len() {
+            chunks.push(Chunk::Inline(data[pos..].to_vec()));
+        }
+
+        PooledLib {
+            id: lib.id(),
+            isae: lib.isae.clone(),
+            code: lib.code.clone(),
+            libs: lib.libs.clone(),
+            meta: lib.meta().cloned(),
+            chunks,
+        }
+    }
+
+    /// Reconstructs the original library from its pooled representation.
+    ///
+    /// This is the only way to execute a pooled library: the VM resolves libraries into [`Lib`]s
+    /// and doesn't access the pool.
+    ///
+    /// # Panics
+    ///
+    /// If the library was not produced by [`ConstPool::add`] on this pool.
+    pub fn materialize(&self, lib: &PooledLib) -> Lib {
+        let mut data = vec![];
+        for chunk in &lib.chunks {
+            match chunk {
+                Chunk::Pooled(idx) => data.extend_from_slice(&self.consts[*idx]),
+                Chunk::Inline(bytes) => data.extend_from_slice(bytes),
+            }
+        }
+        let mut me = Lib::default();
+        me.isae = lib.isae.clone();
+        me.code = lib.code.clone();
+        me.data = SmallBlob::try_from(data).expect("data segment constructed from a valid library");
+        me.libs = lib.libs.clone();
+        if let Some(meta) = lib.meta.clone() {
+            me.set_meta(meta);
+        }
+        me
+    }
+
+    fn push_chunks(
+        &mut self,
+        chunks: &mut Vec<Chunk>,
+        data: &[u8],
+        pos: usize,
+        range: Range<usize>,
+    ) -> usize {
+        if pos < range.start {
+            chunks.push(Chunk::Inline(data[pos..range.start].to_vec()));
+        }
+        chunks.push(Chunk::Pooled(self.intern(&data[range.clone()])));
+        range.end
+    }
+}
+
+/// Collects ranges of the data segment referenced by the library code.
+fn data_refs<Extension>(lib: &Lib) -> Vec<Range<usize>>
+where
+    Extension: InstructionSet,
+{
+    let mut refs = vec![];
+    let mut cursor = Cursor::with(&lib.code, &lib.data, &lib.libs);
+    while !cursor.is_eof() {
+        if Instr::<Extension>::decode(&mut cursor).is_err() {
+            break;
+        }
+        refs.extend(cursor.take_data_ref());
+    }
+    refs
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::data::{ByteStr, MaybeNumber, Number};
+    use crate::isa::{BytesOp, ControlFlowOp, PutOp, ReservedOp};
+    use crate::reg::{Reg32, RegA, RegR, RegS};
+
+    #[test]
+    fn shared_constants() {
+        let generator = Number::from_slice([0x79u8; 64]);
+        let put_gen =
+            |idx| Instr::Put(PutOp::PutR(RegR::R512, idx, Box::new(MaybeNumber::from(generator))));
+        let lib1 = Lib::assemble::<Instr<ReservedOp>>(&[
+            Instr::Put(PutOp::PutA(
+                RegA::A8,
+                Reg32::Reg0,
+                Box::new(MaybeNumber::from(Number::from(7u8))),
+            )),
+            put_gen(Reg32::Reg1),
+            Instr::Bytes(BytesOp::Put(
+                RegS::from(1u8),
+                Box::new(ByteStr::with([0xA5u8; 40])),
+                false,
+            )),
+            Instr::ControlFlow(ControlFlowOp::Ret),
+        ])
+        .unwrap();
+        let lib2 = Lib::assemble::<Instr<ReservedOp>>(&[
+            put_gen(Reg32::Reg4),
+            Instr::Bytes(BytesOp::Put(
+                RegS::from(2u8),
+                Box::new(ByteStr::with([0x5Au8; 8])),
+                false,
+            )),
+        ])
+        .unwrap();
+
+        let mut pool = ConstPool::default();
+        let pooled1 = pool.add::<ReservedOp>(&lib1);
+        let pooled2 = pool.add::<ReservedOp>(&lib2);
+        assert_eq!(pool.len(), 2);
+        assert_eq!(pool.size(), 64 + 40);
+        assert_eq!(pooled1.pooled().collect::<Vec<_>>(), vec![0, 1]);
+        assert_eq!(pooled2.pooled().collect::<Vec<_>>(), vec![0]);
+
+        assert_eq!(pool.materialize(&pooled1).id(), lib1.id());
+        assert_eq!(pool.materialize(&pooled2).data, lib2.data);
+    }
+}
@@ -0,0 +1,124 @@
+// Reference rust implementation of AluVM (arithmetic logic unit virtual machine).
+// To find more on AluVM please check <https://aluvm.org>
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2021-2024 by
+//     Dr Maxim Orlovsky <orlovsky@ubideco.org>
+//
+// Copyright (C) 2021-2022 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2023-2024 UBIDECO Institute. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Client-side interface to remote registries of AluVM libraries.
+
+use core::future::Future;
+use core::pin::Pin;
+use std::error::Error;
+
+use amplify::confinement::{self, Confined, U24 as U24MAX};
+use strict_encoding::{DeserializeError, StrictDeserialize};
+
+use crate::library::{Lib, LibId};
+
+/// Future returned by [`LibRegistry`] methods.
+pub type RegistryFuture<'a, T, E> = Pin<Box<dyn Future<Output = Result<T, E>> + Send + 'a>>;
+
+/// Errors happening during library resolution from a [`LibRegistry`].
+#[derive(Clone, Eq, PartialEq, Debug, Display, From)]
+#[display(doc_comments)]
+pub enum RegistryError<E: Error> {
+    /// registry failure: {0}
+    #[from]
+    Fetch(E),
+
+    /// library {0} is not known to the registry.
+    NotFound(LibId),
+
+    /// the data for library {0} returned by the registry exceed maximum library size.
+    TooLarge(LibId),
+
+    /// the data for library {0} returned by the registry can't be decoded. Details: {1}
+    Decode(LibId, DeserializeError),
+
+    /// the registry has returned library {actual} instead of the requested {expected}.
+    IdMismatch {
+        /// Id of the library which was requested
+        expected: LibId,
+        /// Id of the library returned by the registry
+        actual: LibId,
+    },
+}
+
+impl<E: Error> Error for RegistryError<E> {}
+
+/// Remote registry of AluVM libraries, addressed by their [`LibId`]s.
+///
+/// Implementors provide only the transport ([`LibRegistry::fetch_raw`]); the provided
+/// [`LibRegistry::fetch`] method decodes the returned data and checks that they match the requested
+/// id, so a misbehaving registry can't substitute the code referenced by consensus data.
+pub trait LibRegistry: Sync {
+    /// Transport-level error type of the registry.
+    type Error: Error + Send;
+
+    /// Fetches strict-serialized library data for the library with a given id, returning `None`
+    /// if the registry doesn't know the library.
+    ///
+    /// The returned data are not trusted; use [`LibRegistry::fetch`] to get a verified library.
+    fn fetch_raw(&self, id: LibId) -> RegistryFuture<'_, Option<Vec<u8>>, Self::Error>;
+
+    /// Fetches library with a given id and verifies that the fetched data commit to the same id.
+    fn fetch(&self, id: LibId) -> RegistryFuture<'_, Lib, RegistryError<Self::Error>> {
+        Box::pin(async move {
+            let data = self.fetch_raw(id).await?.ok_or(RegistryError::NotFound(id))?;
+            verify_lib(id, data)
+        })
+    }
+}
+
+/// Decodes strict-serialized library data and verifies that they match the expected library id.
+pub fn verify_lib<E: Error>(id: LibId, data: Vec<u8>) -> Result<Lib, RegistryError<E>> {
+    let data: Confined<Vec<u8>, 0, U24MAX> =
+        Confined::try_from(data).map_err(|_: confinement::Error| RegistryError::TooLarge(id))?;
+    let lib = Lib::from_strict_serialized::<U24MAX>(data)
+        .map_err(|err| RegistryError::Decode(id, err))?;
+    let actual = lib.id();
+    if actual != id {
+        return Err(RegistryError::IdMismatch { expected: id, actual });
+    }
+    Ok(lib)
+}
+
+#[cfg(test)]
+mod test {
+    use std::convert::Infallible;
+
+    use strict_encoding::StrictSerialize;
+
+    use super::*;
+
+    #[test]
+    fn verify_id() {
+        let lib = Lib::with("ALU", vec![0x00], vec![], none!()).unwrap();
+        let other = Lib::with("ALU", vec![0x02], vec![], none!()).unwrap();
+        let data = lib.to_strict_serialized::<U24MAX>().unwrap().to_vec();
+
+        let verified = verify_lib::<Infallible>(lib.id(), data.clone()).unwrap();
+        assert_eq!(verified.id(), lib.id());
+        assert_eq!(
+            verify_lib::<Infallible>(other.id(), data).unwrap_err(),
+            RegistryError::IdMismatch { expected: other.id(), actual: lib.id() }
+        );
+    }
+}
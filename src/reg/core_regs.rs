@@ -44,6 +44,18 @@ use crate::library::LibSite;
 /// Equals to 2^16 (limited by `cy0` and `cp0` bit size)
 pub const CALL_STACK_SIZE: usize = 1 << 16;
 
+/// Configuration of a single microprocessor/VM core
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug, Default)]
+pub struct CoreConfig {
+    /// Halts program execution with `st0` set to `false` once an instruction reads a register
+    /// which is in uninitialized state, instead of propagating `None` values through the
+    /// computation.
+    ///
+    /// Registers read by an instruction are determined by [`InstructionSet::required_src_regs`];
+    /// the check is performed before the instruction is executed.
+    pub halt_on_uninit: bool,
+}
+
 /// Structure keeping state of all registers in a single microprosessor/VM core
 #[derive(Clone)]
 pub struct CoreRegs {
@@ -89,6 +101,14 @@ pub struct CoreRegs {
     /// script.
     cy0: u16,
 
+    /// Counts number of executed instructions. The register is read-only for the programs and can
+    /// be accessed with `icnt` instruction.
+    ///
+    /// # See also
+    ///
+    /// - [`CoreRegs::ca0`] register
+    ic0: u64,
+
     /// Complexity accumulator / counter.
     ///
     /// Each instruction has associated computational complexity level. This register sums
@@ -116,6 +136,9 @@ pub struct CoreRegs {
 
     /// Defines "top" of the call stack
     cp0: u16,
+
+    /// Core configuration
+    config: CoreConfig,
 }
 
 impl Default for CoreRegs {
@@ -153,10 +176,12 @@ impl Default for CoreRegs {
 
             st0: true,
             cy0: 0,
+            ic0: 0,
             ca0: 0,
             cl0: None,
             cs0: vec![LibSite::default(); CALL_STACK_SIZE],
             cp0: 0,
+            config: Default::default(),
         }
     }
 }
@@ -169,6 +194,15 @@ impl CoreRegs {
     #[inline]
     pub fn new() -> CoreRegs { CoreRegs::default() }
 
+    /// Initializes register state in the same way as [`CoreRegs::new`], using the provided core
+    /// configuration.
+    #[inline]
+    pub fn with(config: CoreConfig) -> CoreRegs { CoreRegs { config, ..Default::default() } }
+
+    /// Returns configuration of the core
+    #[inline]
+    pub fn config(&self) -> CoreConfig { self.config }
+
     pub(crate) fn jmp(&mut self) -> Result<(), ()> {
         self.cy0
             .checked_add(1)
@@ -435,7 +469,16 @@ impl CoreRegs {
         self.set_n(reg3.into(), dst, reg_val);
     }
 
-    /// Accumulates complexity of the instruction into `ca0`.
+    /// Returns number of instructions executed so far (value of `ic0` register).
+    #[inline]
+    pub fn instr_count(&self) -> u64 { self.ic0 }
+
+    /// Returns complexity of the instructions executed so far (value of `ca0` register).
+    #[inline]
+    pub fn complexity_acc(&self) -> u64 { self.ca0 }
+
+    /// Accumulates complexity of the instruction into `ca0` and increments executed instruction
+    /// counter `ic0`.
     ///
     /// Sets `st0` to `false` if the complexity limit is reached or exceeded. Otherwise, does not
     /// modify `st0` value.
@@ -446,7 +489,13 @@ impl CoreRegs {
     /// this limit
     #[inline]
     pub fn acc_complexity(&mut self, instr: impl InstructionSet) -> bool {
-        self.ca0 = self.ca0.saturating_add(instr.complexity());
+        self.acc_instr_complexity(instr.complexity())
+    }
+
+    /// Does the same as [`CoreRegs::acc_complexity`] for an instruction with a given complexity.
+    pub(crate) fn acc_instr_complexity(&mut self, complexity: u64) -> bool {
+        self.ic0 = self.ic0.saturating_add(1);
+        self.ca0 = self.ca0.saturating_add(complexity);
         if let Some(limit) = self.cl0 {
             if self.ca0 >= limit {
                 self.st0 = false;
@@ -459,6 +508,31 @@ impl CoreRegs {
         }
     }
 
+    /// Checks whether all source registers of the instruction are initialized, if the core is
+    /// configured with [`CoreConfig::halt_on_uninit`]. Otherwise, does nothing.
+    ///
+    /// Only the registers listed by [`InstructionSet::required_src_regs`] are checked, such that
+    /// instructions explicitly handling uninitialized registers (like `ifn`) are not halted.
+    ///
+    /// Sets `st0` to `false` if any of the source registers is in uninitialized state.
+    ///
+    /// # Returns
+    ///
+    /// `false` if the program execution must be halted
+    pub fn check_src_regs(&mut self, instr: &impl InstructionSet) -> bool {
+        if !self.config.halt_on_uninit {
+            return true;
+        }
+        let uninit = instr.required_src_regs().into_iter().any(|reg| match self.get(reg) {
+            RegValue::Number(val) => val.is_none(),
+            RegValue::String(val) => val.is_none(),
+        });
+        if uninit {
+            self.st0 = false;
+        }
+        !uninit
+    }
+
     /// Returns vale of `st0` register
     #[inline]
     pub fn status(&self) -> bool { self.st0 }
@@ -482,6 +556,7 @@ impl Debug for CoreRegs {
         write!(f, "{}CTRL:{}\t", sect, reset)?;
         write!(f, "{}st0{}={}{} ", reg, eq, val, self.st0)?;
         write!(f, "{}cy0{}={}{} ", reg, eq, val, self.cy0)?;
+        write!(f, "{}ic0{}={}{} ", reg, eq, val, self.ic0)?;
         write!(f, "{}ca0{}={}{} ", reg, eq, val, self.ca0)?;
         let cl = self.cl0.map(|v| v.to_string()).unwrap_or_else(|| "~".to_string());
         write!(f, "{}cl0{}={}{} ", reg, eq, val, cl)?;
@@ -835,4 +910,72 @@ mod test {
 
         eprintln!("{regs:#?}");
     }
+
+    #[test]
+    fn halt_on_uninit() {
+        use crate::isa::{ArithmeticOp, Instr, IntFlags};
+
+        let instr = Instr::<crate::isa::ReservedOp>::Arithmetic(ArithmeticOp::AddA(
+            IntFlags::unsigned_checked(),
+            RegA::A8,
+            Reg32::Reg0,
+            Reg32::Reg1,
+        ));
+
+        let mut regs = CoreRegs::new();
+        assert!(regs.check_src_regs(&instr));
+        assert!(regs.st0);
+
+        let mut regs = CoreRegs::with(CoreConfig { halt_on_uninit: true });
+        regs.set_n(RegA::A8, Reg32::Reg0, 1u8);
+        assert!(!regs.check_src_regs(&instr));
+        assert!(!regs.st0);
+
+        let mut regs = CoreRegs::with(CoreConfig { halt_on_uninit: true });
+        regs.set_n(RegA::A8, Reg32::Reg0, 1u8);
+        regs.set_n(RegA::A8, Reg32::Reg1, 2u8);
+        assert!(regs.check_src_regs(&instr));
+        assert!(regs.st0);
+    }
+
+    #[test]
+    fn halt_on_uninit_families() {
+        use crate::isa::{
+            BytesOp, CmpOp, ExecStep, Instr, InstructionSet, MoveOp, NoneEqFlag, ReservedOp,
+        };
+        use crate::library::{Cursor, Lib, LibId, LibSite};
+
+        let strict = CoreConfig { halt_on_uninit: true };
+        let code: [Instr<ReservedOp>; 6] = [
+            Instr::Move(MoveOp::DupA(RegA::A8, Reg32::Reg0, Reg32::Reg1)),
+            Instr::Move(MoveOp::MovR(RegR::R256, Reg32::Reg0, Reg32::Reg1)),
+            Instr::Cmp(CmpOp::EqA(NoneEqFlag::Equal, RegA::A8, Reg32::Reg0, Reg32::Reg1)),
+            Instr::Cmp(CmpOp::IfZR(RegR::R256, Reg32::Reg0)),
+            Instr::Bytes(BytesOp::Mov(RegS::from(0), RegS::from(1))),
+            Instr::Bytes(BytesOp::Eq(RegS::from(0), RegS::from(1))),
+        ];
+        for instr in &code {
+            let mut regs = CoreRegs::with(strict);
+            assert!(regs.check_src_regs(instr), "{}", instr);
+            // Family-level operations are checked directly by the dispatch table and by the
+            // superinstructions of pre-decoded libraries
+            let accepted = match instr {
+                Instr::Move(op) => regs.check_src_regs(op),
+                Instr::Cmp(op) => regs.check_src_regs(op),
+                Instr::Bytes(op) => regs.check_src_regs(op),
+                _ => unreachable!(),
+            };
+            assert!(accepted, "{}", instr);
+            assert!(regs.st0);
+        }
+
+        let lib = Lib::assemble(&code).unwrap();
+        let mut cursor = Cursor::with(&lib.code, &lib.data, &lib.libs);
+        let mut regs = CoreRegs::with(strict);
+        for instr in &code {
+            let site = LibSite::with(0, LibId::default());
+            let step = Instr::<ReservedOp>::step(&mut cursor, &mut regs, site, &());
+            assert_eq!(step, Some(ExecStep::Next), "{}", instr);
+        }
+    }
 }
@@ -118,7 +118,7 @@ impl RegA {
     ];
 
     /// Constructs [`RegA`] object for a provided requirement for register bit size
-    pub fn with(bits: u16) -> Option<Self> {
+    pub const fn with(bits: u16) -> Option<Self> {
         Some(match bits {
             8 => RegA::A8,
             16 => RegA::A16,
@@ -224,7 +224,7 @@ impl NumericRegister for RegA2 {
 
 impl RegA2 {
     /// Constructs [`RegA2`] object for a provided requirement for register bit size
-    pub fn with(bits: u16) -> Option<Self> {
+    pub const fn with(bits: u16) -> Option<Self> {
         Some(match bits {
             8 => RegA2::A8,
             16 => RegA2::A16,
@@ -353,7 +353,7 @@ impl RegF {
     ];
 
     /// Constructs [`RegF`] object for a provided requirement for register bit size
-    pub fn with(bits: u16, use_bfloat16: bool) -> Option<Self> {
+    pub const fn with(bits: u16, use_bfloat16: bool) -> Option<Self> {
         Some(match bits {
             16 => {
                 if use_bfloat16 {
@@ -483,7 +483,7 @@ impl RegR {
 
     /// Constructs [`RegR`] object for a provided requirement for register bit size
     #[inline]
-    pub fn with(bits: u16) -> Option<Self> {
+    pub const fn with(bits: u16) -> Option<Self> {
         Some(match bits {
             128 => RegR::R128,
             160 => RegR::R160,
@@ -562,7 +562,7 @@ impl Register for RegAll {
 impl RegAll {
     /// Returns inner A-register type, if any
     #[inline]
-    pub fn reg_a(self) -> Option<RegA> {
+    pub const fn reg_a(self) -> Option<RegA> {
         match self {
             RegAll::A(a) => Some(a),
             _ => None,
@@ -571,7 +571,7 @@ impl RegAll {
 
     /// Returns inner F-register type, if any
     #[inline]
-    pub fn reg_f(self) -> Option<RegF> {
+    pub const fn reg_f(self) -> Option<RegF> {
         match self {
             RegAll::F(f) => Some(f),
             _ => None,
@@ -580,7 +580,7 @@ impl RegAll {
 
     /// Returns inner R-register type, if any
     #[inline]
-    pub fn reg_r(self) -> Option<RegR> {
+    pub const fn reg_r(self) -> Option<RegR> {
         match self {
             RegAll::R(r) => Some(r),
             _ => None,
@@ -715,7 +715,7 @@ impl NumericRegister for RegAFR {
 impl RegAFR {
     /// Returns inner A-register type, if any
     #[inline]
-    pub fn reg_a(self) -> Option<RegA> {
+    pub const fn reg_a(self) -> Option<RegA> {
         match self {
             RegAFR::A(a) => Some(a),
             _ => None,
@@ -724,7 +724,7 @@ impl RegAFR {
 
     /// Returns inner F-register type, if any
     #[inline]
-    pub fn reg_f(self) -> Option<RegF> {
+    pub const fn reg_f(self) -> Option<RegF> {
         match self {
             RegAFR::F(f) => Some(f),
             _ => None,
@@ -733,7 +733,7 @@ impl RegAFR {
 
     /// Returns inner R-register type, if any
     #[inline]
-    pub fn reg_r(self) -> Option<RegR> {
+    pub const fn reg_r(self) -> Option<RegR> {
         match self {
             RegAFR::R(r) => Some(r),
             _ => None,
@@ -863,7 +863,7 @@ impl NumericRegister for RegAF {
 impl RegAF {
     /// Returns inner A-register type, if any
     #[inline]
-    pub fn reg_a(self) -> Option<RegA> {
+    pub const fn reg_a(self) -> Option<RegA> {
         match self {
             RegAF::A(a) => Some(a),
             RegAF::F(_) => None,
@@ -872,7 +872,7 @@ impl RegAF {
 
     /// Returns inner F-register type, if any
     #[inline]
-    pub fn reg_f(self) -> Option<RegF> {
+    pub const fn reg_f(self) -> Option<RegF> {
         match self {
             RegAF::A(_) => None,
             RegAF::F(f) => Some(f),
@@ -978,7 +978,7 @@ impl RegAR {
 
     /// Returns inner A-register type, if any
     #[inline]
-    pub fn reg_a(self) -> Option<RegA> {
+    pub const fn reg_a(self) -> Option<RegA> {
         match self {
             RegAR::A(a) => Some(a),
             RegAR::R(_) => None,
@@ -987,7 +987,7 @@ impl RegAR {
 
     /// Returns inner R-register type, if any
     #[inline]
-    pub fn reg_r(self) -> Option<RegR> {
+    pub const fn reg_r(self) -> Option<RegR> {
         match self {
             RegAR::A(_) => None,
             RegAR::R(r) => Some(r),
@@ -1062,10 +1062,11 @@ impl Register for RegBlockAR {
 impl RegBlockAR {
     /// Converts value into specific register matching the provided bit dimension. If the register
     /// with the given dimension does not exists, returns `None`.
-    pub fn into_reg(self, bits: u16) -> Option<RegAR> {
-        match self {
-            RegBlockAR::A => RegA::with(bits).map(RegAR::A),
-            RegBlockAR::R => RegR::with(bits).map(RegAR::R),
+    pub const fn into_reg(self, bits: u16) -> Option<RegAR> {
+        match (self, RegA::with(bits), RegR::with(bits)) {
+            (RegBlockAR::A, Some(reg), _) => Some(RegAR::A(reg)),
+            (RegBlockAR::R, _, Some(reg)) => Some(RegAR::R(reg)),
+            _ => None,
         }
     }
 }
@@ -1109,11 +1110,12 @@ impl Register for RegBlockAFR {
 impl RegBlockAFR {
     /// Converts value into specific register matching the provided bit dimension. If the register
     /// with the given dimension does not exists, returns `None`.
-    pub fn into_reg(self, bits: u16) -> Option<RegAFR> {
-        match self {
-            RegBlockAFR::A => RegA::with(bits).map(RegAFR::A),
-            RegBlockAFR::F => RegF::with(bits, false).map(RegAFR::F),
-            RegBlockAFR::R => RegR::with(bits).map(RegAFR::R),
+    pub const fn into_reg(self, bits: u16) -> Option<RegAFR> {
+        match (self, RegA::with(bits), RegF::with(bits, false), RegR::with(bits)) {
+            (RegBlockAFR::A, Some(reg), _, _) => Some(RegAFR::A(reg)),
+            (RegBlockAFR::F, _, Some(reg), _) => Some(RegAFR::F(reg)),
+            (RegBlockAFR::R, _, _, Some(reg)) => Some(RegAFR::R(reg)),
+            _ => None,
         }
     }
 }
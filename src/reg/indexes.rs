@@ -202,7 +202,7 @@ impl Reg32 {
 
     /// Returns `usize` representation of the register index
     #[inline]
-    pub fn to_usize(self) -> usize { self as u8 as usize }
+    pub const fn to_usize(self) -> usize { self as u8 as usize }
 }
 
 impl Register for Reg32 {
@@ -367,6 +367,10 @@ impl Reg16 {
         Reg16::Reg14,
         Reg16::Reg15,
     ];
+
+    /// Returns `usize` representation of the register index
+    #[inline]
+    pub const fn to_usize(self) -> usize { self as u8 as usize }
 }
 
 impl Register for Reg16 {
@@ -478,6 +482,10 @@ impl Reg8 {
         Reg8::Reg6,
         Reg8::Reg7,
     ];
+
+    /// Returns `usize` representation of the register index
+    #[inline]
+    pub const fn to_usize(self) -> usize { self as u8 as usize }
 }
 
 impl Register for Reg8 {
@@ -27,7 +27,7 @@ mod core_regs;
 mod families;
 mod indexes;
 
-pub use core_regs::{CoreRegs, CALL_STACK_SIZE};
+pub use core_regs::{CoreConfig, CoreRegs, CALL_STACK_SIZE};
 pub use families::{
     NumericRegister, RegA, RegA2, RegAF, RegAFR, RegAR, RegAll, RegBlock, RegBlockAFR, RegBlockAR,
     RegF, RegR,
@@ -74,7 +74,7 @@ impl Reg {
     }
 
     /// Returns family ([`RegBlock`]) of the register
-    pub fn family(self) -> RegBlock {
+    pub const fn family(self) -> RegBlock {
         match self {
             Reg::A(_, _) => RegBlock::A,
             Reg::F(_, _) => RegBlock::F,
@@ -84,7 +84,7 @@ impl Reg {
     }
 
     /// Returns specific register ([`RegAll`]) of the register
-    pub fn register(self) -> RegAll {
+    pub const fn register(self) -> RegAll {
         match self {
             Reg::A(reg, _) => RegAll::A(reg),
             Reg::F(reg, _) => RegAll::F(reg),
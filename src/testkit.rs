@@ -0,0 +1,281 @@
+// Reference rust implementation of AluVM (arithmetic logic unit virtual machine).
+// To find more on AluVM please check <https://aluvm.org>
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2021-2024 by
+//     Dr Maxim Orlovsky <orlovsky@ubideco.org>
+//
+// Copyright (C) 2021-2022 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2023-2024 UBIDECO Institute. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Reusable round-trip checks for instruction set encodings.
+//!
+//! The checks are generic over [`InstructionSet`], such that ISA extensions defined outside of
+//! this crate can be verified with the same harness which is used for the core ISA:
+//!
+//! - [`check_instr`] verifies that `decode(encode(instr)) == instr`;
+//! - [`check_bytecode`] verifies that `encode(decode(bytes)) == bytes`;
+//! - [`check_random_bytecode`] runs [`check_bytecode`] against pseudo-random bytecode for each of
+//!   the opcodes covered by the instruction set, reproducible from a given seed.
+
+use core::slice;
+
+use amplify::hex::ToHex;
+use amplify::num::u3;
+
+use crate::isa::InstructionSet;
+use crate::library::{AssemblerError, Cursor, Lib, LibSeg};
+
+/// Maximal number of argument bytes generated by [`check_random_bytecode`] after each opcode.
+pub const RANDOM_ARGS_MAX_LEN: usize = 48;
+
+/// Errors detected by the encoding round-trip checks
+#[derive(Clone, Eq, PartialEq, Debug, Display)]
+#[display(doc_comments)]
+pub enum RoundtripError {
+    /// instruction `{0}` can't be encoded. Details: {1}
+    Encode(String, AssemblerError),
+
+    /// bytecode `{0}` can't be decoded.
+    Decode(String),
+
+    /// instruction `{original}` was decoded back as `{decoded}`.
+    InstrMismatch {
+        /// Original instruction
+        original: String,
+        /// Instruction(s) decoded from the original instruction bytecode
+        decoded: String,
+    },
+
+    /// bytecode `{original}` decoded as `{instr}` was re-encoded into a different bytecode
+    /// `{reencoded}`.
+    BytesMismatch {
+        /// Original bytecode
+        original: String,
+        /// Instruction decoded from the original bytecode
+        instr: String,
+        /// Bytecode produced by encoding the decoded instruction
+        reencoded: String,
+    },
+}
+
+impl ::std::error::Error for RoundtripError {}
+
+/// Checks that the instruction is decoded from its own bytecode into an equal instruction.
+pub fn check_instr<Isa>(instr: &Isa) -> Result<(), RoundtripError>
+where
+    Isa: InstructionSet + PartialEq,
+{
+    let lib = Lib::assemble(slice::from_ref(instr))
+        .map_err(|err| RoundtripError::Encode(instr.to_string(), err))?;
+    let decoded = lib
+        .disassemble::<Isa>()
+        .map_err(|_| RoundtripError::Decode(lib.code.as_slice().to_hex()))?;
+    if decoded.as_slice() != slice::from_ref(instr) {
+        return Err(RoundtripError::InstrMismatch {
+            original: instr.to_string(),
+            decoded: decoded.iter().map(Isa::to_string).collect::<Vec<_>>().join("; "),
+        });
+    }
+    Ok(())
+}
+
+/// Decodes the first instruction from the bytecode and checks that the instruction is encoded back
+/// into exactly the same bytes as were consumed by the decoder, and that the produced bytecode
+/// decodes into the same instruction. Bytes following the first instruction are ignored.
+///
+/// Returns the decoded instruction.
+///
+/// # Panics
+///
+/// If the length of the bytecode or data exceeds the maximum segment size.
+pub fn check_bytecode<Isa>(code: &[u8], data: &[u8], libs: &LibSeg) -> Result<Isa, RoundtripError>
+where
+    Isa: InstructionSet + PartialEq,
+{
+    let (instr, consumed) = decode_consumed::<Isa>(code, data, libs)?;
+    let lib = Lib::assemble(slice::from_ref(&instr))
+        .map_err(|err| RoundtripError::Encode(instr.to_string(), err))?;
+    if lib.code.as_slice() != consumed.as_slice() {
+        return Err(RoundtripError::BytesMismatch {
+            original: consumed.to_hex(),
+            instr: instr.to_string(),
+            reencoded: lib.code.as_slice().to_hex(),
+        });
+    }
+    check_instr(&instr)?;
+    Ok(instr)
+}
+
+/// Decodes the first instruction from the bytecode, returning it together with the bytes consumed
+/// by the decoder.
+fn decode_consumed<Isa>(
+    code: &[u8],
+    data: &[u8],
+    libs: &LibSeg,
+) -> Result<(Isa, Vec<u8>), RoundtripError>
+where
+    Isa: InstructionSet,
+{
+    let mut reader = Cursor::with(code, data, libs);
+    let instr = Isa::decode(&mut reader).map_err(|_| RoundtripError::Decode(code.to_hex()))?;
+    let (pos, bits) = reader.offset();
+
+    // Unused high bits of a partially consumed byte are not a part of the instruction
+    let mut consumed = code[..pos as usize].to_vec();
+    if bits != u3::MIN {
+        consumed.push(code[pos as usize] & ((1u8 << bits.to_u8()) - 1));
+    }
+    Ok((instr, consumed))
+}
+
+/// Detects whether the bytecode differs from the canonical encoding of the instruction decoded
+/// from it only in the reserved bits (like high bits of the bytes holding `s` register indexes):
+/// bits which are ignored by the decoder and which are always set to zero by the encoder.
+fn differs_in_reserved_bits<Isa>(code: &[u8], libs: &LibSeg) -> bool
+where
+    Isa: InstructionSet + PartialEq,
+{
+    let Ok((instr, consumed)) = decode_consumed::<Isa>(code, &[], libs) else {
+        return false;
+    };
+    let Ok(lib) = Lib::assemble(slice::from_ref(&instr)) else {
+        return false;
+    };
+    if lib.code.len() != consumed.len() || check_instr(&instr).is_err() {
+        return false;
+    }
+    let ignored = |pos: usize, mask: u8| {
+        let mut flipped = code.to_vec();
+        flipped[pos] ^= mask;
+        Isa::decode(&mut Cursor::with(&flipped, &[], libs)).ok().as_ref() == Some(&instr)
+    };
+    consumed.iter().zip(lib.code.as_slice()).enumerate().all(|(pos, (orig, canonical))| {
+        let diff = orig ^ canonical;
+        diff & canonical == 0
+            && (0..8)
+                .map(|bit| 1u8 << bit)
+                .filter(|mask| diff & mask != 0)
+                .all(|mask| ignored(pos, mask))
+    })
+}
+
+/// Runs [`check_bytecode`] on `rounds` pseudo-random bytecode strings for each opcode from
+/// [`Bytecode::instr_range`] of the instruction set. Bytecode which can't be decoded is skipped.
+///
+/// The generated bytecode is fully determined by the `seed`, so a failure can be reproduced by
+/// re-running the check with the same seed. Random references to the data and libs segments
+/// can't be re-encoded into the same bytes (the segments are assembled anew by the encoder), thus
+/// bytecode of instructions referencing the segments (like `put` or `call`) is skipped as well,
+/// and such instructions can be covered only by [`check_instr`]. Opcodes which are decoded into
+/// an instruction encoded with some other opcode (like `nop`) are skipped too. Random bytecode is
+/// also allowed to differ from its re-encoded form in the bits which are ignored by the decoder,
+/// since the encoder always sets them to zero.
+///
+/// [`Bytecode::instr_range`]: crate::isa::Bytecode::instr_range
+pub fn check_random_bytecode<Isa>(seed: u64, rounds: usize) -> Result<(), RoundtripError>
+where
+    Isa: InstructionSet + PartialEq,
+{
+    let libs = LibSeg::default();
+    let mut rng = XorShift::with(seed);
+    for opcode in Isa::instr_range() {
+        for _ in 0..rounds {
+            let mut code = Vec::with_capacity(RANDOM_ARGS_MAX_LEN + 1);
+            code.push(opcode);
+            code.extend((0..RANDOM_ARGS_MAX_LEN).map(|_| rng.next_u8()));
+            let mut reader = Cursor::with(&code, &[], &libs);
+            let Ok(instr) = Isa::decode(&mut reader) else {
+                continue;
+            };
+            if instr.instr_byte() != opcode
+                || reader.take_data_ref().is_some()
+                || reader.take_lib_ref().is_some()
+            {
+                continue;
+            }
+            match check_bytecode::<Isa>(&code, &[], &libs) {
+                Ok(_) => {}
+                Err(RoundtripError::BytesMismatch { .. })
+                    if differs_in_reserved_bits::<Isa>(&code, &libs) => {}
+                Err(err) => return Err(err),
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Deterministic pseudo-random generator (xorshift64) producing random instruction arguments.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct XorShift(u64);
+
+impl XorShift {
+    /// Constructs generator from a seed. Zero seed is replaced with a non-zero one, since
+    /// xorshift never leaves zero state.
+    pub fn with(seed: u64) -> Self { XorShift(seed | 1) }
+
+    /// Returns next pseudo-random byte
+    pub fn next_u8(&mut self) -> u8 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        (self.0 >> 56) as u8
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::isa::{ConstOp, ControlFlowOp, Instr, IntrospectOp, MoveOp, ReservedOp};
+    use crate::reg::{Reg16, Reg32, RegA, RegF};
+
+    #[test]
+    fn core_instr_roundtrip() {
+        let code: [Instr<ReservedOp>; 7] = [
+            Instr::ControlFlow(ControlFlowOp::Ret),
+            Instr::ControlFlow(ControlFlowOp::Jif(0x1234)),
+            Instr::Move(MoveOp::SwpA(RegA::A64, Reg32::Reg3, Reg32::Reg31)),
+            Instr::Move(MoveOp::DupF(RegF::F32, Reg32::Reg0, Reg32::Reg7)),
+            Instr::Const(ConstOp::MaxA(RegA::A128, Reg32::Reg12)),
+            Instr::Introspect(IntrospectOp::Site(Reg16::Reg1, Reg16::Reg15)),
+            Instr::Introspect(IntrospectOp::Icnt(Reg32::Reg8)),
+        ];
+        for instr in &code {
+            check_instr(instr).unwrap();
+        }
+    }
+
+    #[test]
+    fn core_bytecode_roundtrip() {
+        let libs = LibSeg::default();
+        let lib = Lib::assemble(&[Instr::<ReservedOp>::Move(MoveOp::MovA(
+            RegA::A16,
+            Reg32::Reg1,
+            Reg32::Reg2,
+        ))])
+        .unwrap();
+        let instr = check_bytecode::<Instr<ReservedOp>>(&lib.code, &[], &libs).unwrap();
+        assert_eq!(instr, Instr::Move(MoveOp::MovA(RegA::A16, Reg32::Reg1, Reg32::Reg2)));
+    }
+
+    #[test]
+    fn reserved_random_roundtrip() { check_random_bytecode::<ReservedOp>(0xA1B2_C3D4, 4).unwrap(); }
+
+    #[test]
+    fn core_random_roundtrip() {
+        check_random_bytecode::<Instr<ReservedOp>>(0xA1B2_C3D4, 8).unwrap();
+    }
+}
@@ -25,11 +25,267 @@
 
 #[cfg(all(feature = "alloc", not(feature = "std")))]
 use alloc::boxed::Box;
+use alloc::collections::BTreeMap;
 use core::marker::PhantomData;
+use core::num::NonZeroU64;
+use core::ops::Deref;
 
+use crate::data::{ByteStr, Number};
 use crate::isa::{Instr, InstructionSet, ReservedOp};
-use crate::library::{Lib, LibId, LibSite};
-use crate::reg::CoreRegs;
+use crate::library::{DecodedLib, Lib, LibId, LibSite};
+use crate::reg::{CoreConfig, CoreRegs, Reg32, RegA, RegS};
+
+/// Maximal length of a single chunk streamed into a string register by [`Vm::exec_streamed`]
+pub const STREAM_CHUNK_MAX_LEN: usize = u16::MAX as usize;
+
+/// Errors happening during sandboxed program execution
+#[cfg(feature = "sandbox")]
+#[derive(Clone, PartialEq, Eq, Hash, Debug, Display)]
+#[display(doc_comments)]
+pub enum ExecError {
+    /// internal error during program execution: {0}
+    InternalError(String),
+}
+
+#[cfg(feature = "sandbox")]
+impl ::std::error::Error for ExecError {}
+
+/// Errors happening during streaming of the program input
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug, Display)]
+#[display(doc_comments)]
+pub enum StreamError {
+    /// input chunk #{chunk} has length {len} exceeding maximal string register size
+    ChunkTooLarge {
+        /// Number of the chunk in the input
+        chunk: u32,
+        /// Length of the chunk
+        len: usize,
+    },
+
+    /// input has more than 2^32 chunks
+    TooManyChunks,
+}
+
+#[cfg(feature = "std")]
+impl ::std::error::Error for StreamError {}
+
+/// Binding of the input streamed with [`Vm::exec_streamed`] to the registers.
+///
+/// Consequent chunks are placed into a window of string registers in a round-robin way, starting
+/// from the `first` register, such that the program can access the last `window` chunks (for
+/// instance, to match patterns crossing chunk boundaries). Before each chunk is processed, the
+/// number of the chunk is put into `a32[index]`, and `a8[index]` is set to `1` for the last chunk
+/// of the input and to `0` otherwise.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct StreamBinding {
+    first: RegS,
+    window: u8,
+    index: Reg32,
+}
+
+impl StreamBinding {
+    /// Constructs binding rotating over `window` string registers starting from `first`. The
+    /// window wraps around the last `s` register.
+    ///
+    /// Returns `None` if the window is empty or exceeds the number of string registers.
+    pub fn with(first: RegS, window: u8, index: Reg32) -> Option<Self> {
+        if !(1..=16).contains(&window) {
+            return None;
+        }
+        Some(StreamBinding { first, window, index })
+    }
+
+    /// Returns string register receiving a given chunk
+    pub fn chunk_reg(&self, chunk: u32) -> RegS {
+        RegS::from((u8::from(self.first) as u32 + chunk % self.window as u32) as u8)
+    }
+
+    /// Returns index of the `a32` and `a8` registers holding chunk number and last-chunk flag
+    #[inline]
+    pub fn index(&self) -> Reg32 { self.index }
+}
+
+/// Units in which the period between preemption points is measured
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug, Default)]
+pub enum FuelUnit {
+    /// Number of executed instructions (value of `ic0` register)
+    #[default]
+    Instructions,
+
+    /// Accumulated complexity of executed instructions (value of `ca0` register)
+    Complexity,
+}
+
+/// Preemption points, invoking user-provided callback each time the program execution has spent
+/// the given amount of fuel.
+///
+/// The callback receives the current state of the registers and returns whether the execution
+/// must continue. Returning `false` halts the program, setting `st0` to `false`. This allows
+/// synchronous embedders to check deadlines or external cancellation signals during long-running
+/// programs.
+pub struct Preemption<'cb> {
+    unit: FuelUnit,
+    period: NonZeroU64,
+    next: u64,
+    callback: Box<dyn FnMut(&CoreRegs) -> bool + 'cb>,
+}
+
+impl<'cb> Preemption<'cb> {
+    /// Constructs preemption points invoking `callback` every `period` units of fuel.
+    pub fn with(
+        unit: FuelUnit,
+        period: NonZeroU64,
+        callback: impl FnMut(&CoreRegs) -> bool + 'cb,
+    ) -> Self {
+        Preemption { unit, period, next: period.get(), callback: Box::new(callback) }
+    }
+
+    /// Returns units in which the fuel is measured
+    #[inline]
+    pub fn unit(&self) -> FuelUnit { self.unit }
+
+    /// Returns amount of fuel between two consequent preemption points
+    #[inline]
+    pub fn period(&self) -> NonZeroU64 { self.period }
+
+    /// Invokes callback if the preemption point is reached.
+    ///
+    /// # Returns
+    ///
+    /// `false` if the callback has requested to halt the program execution
+    pub(crate) fn check(&mut self, regs: &CoreRegs) -> bool {
+        let fuel = match self.unit {
+            FuelUnit::Instructions => regs.instr_count(),
+            FuelUnit::Complexity => regs.complexity_acc(),
+        };
+        if fuel < self.next {
+            return true;
+        }
+        let period = self.period.get();
+        self.next = (fuel / period).saturating_add(1).saturating_mul(period);
+        (self.callback)(regs)
+    }
+}
+
+/// Execution statistics of a single library, collected by [`Profiler`]
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug, Default)]
+pub struct LibStats {
+    /// Number of times the library code was entered (including returns from calls to other
+    /// libraries)
+    pub calls: u64,
+    /// Number of instructions executed within the library
+    pub instructions: u64,
+    /// Accumulated complexity of the instructions executed within the library
+    pub complexity: u64,
+}
+
+/// Hook invoked by [`Profiler`] once a library becomes hot
+type HotHook<'cb> = Box<dyn FnMut(LibId, &LibStats) + 'cb>;
+
+/// Execution profiler, accumulating per-library statistics over multiple program runs.
+///
+/// Once a library is entered `threshold` times, the profiler invokes the hook with the library
+/// id and its statistics. Long-running hosts can use the hook to prepare an optimized
+/// representation of hot libraries (for instance, [`DecodedLib`] with fused superinstructions),
+/// return it from the library resolver of [`Vm::exec_with`] afterwards (see [`ExecLib`]) and
+/// [`Profiler::reset`] the library statistics.
+pub struct Profiler<'cb> {
+    threshold: NonZeroU64,
+    stats: BTreeMap<LibId, LibStats>,
+    on_hot: HotHook<'cb>,
+}
+
+impl<'cb> Profiler<'cb> {
+    /// Constructs profiler invoking `on_hot` hook for each library entered `threshold` times.
+    pub fn with(threshold: NonZeroU64, on_hot: impl FnMut(LibId, &LibStats) + 'cb) -> Self {
+        Profiler { threshold, stats: BTreeMap::new(), on_hot: Box::new(on_hot) }
+    }
+
+    /// Returns number of library entries after which the library is considered hot
+    #[inline]
+    pub fn threshold(&self) -> NonZeroU64 { self.threshold }
+
+    /// Returns statistics collected for a library, if the library was executed
+    #[inline]
+    pub fn stats(&self, id: LibId) -> Option<&LibStats> { self.stats.get(&id) }
+
+    /// Iterates over statistics of all executed libraries
+    pub fn iter(&self) -> impl Iterator<Item = (LibId, &LibStats)> + '_ {
+        self.stats.iter().map(|(id, stats)| (*id, stats))
+    }
+
+    /// Removes statistics collected for a library, returning them. If the library gets executed
+    /// again, the hook will be invoked once more after it reaches the threshold.
+    #[inline]
+    pub fn reset(&mut self, id: LibId) -> Option<LibStats> { self.stats.remove(&id) }
+
+    fn record(&mut self, id: LibId, instructions: u64, complexity: u64) {
+        let stats = self.stats.entry(id).or_default();
+        stats.calls += 1;
+        stats.instructions += instructions;
+        stats.complexity += complexity;
+        if stats.calls == self.threshold.get() {
+            (self.on_hot)(id, stats);
+        }
+    }
+}
+
+/// Library code which can be returned by the library resolver to the [`Vm`] for the execution
+/// with [`Vm::exec_with`], [`Vm::exec_profiled`] or [`Vm::exec_streamed`].
+///
+/// Implemented by [`Lib`], by `MappedLib` executing code directly from a memory-mapped file and,
+/// for the instruction sets based on [`Instr`], by [`DecodedLib`]; thus the resolver may return
+/// a pre-decoded representation of a hot library instead of its bytecode. The resolver may return
+/// the code by reference or by any other pointer type dereferencing into it.
+pub trait ExecLib<Isa>
+where
+    Isa: InstructionSet,
+{
+    /// Executes library code starting at entrypoint, invoking `preemption` callback, if provided.
+    ///
+    /// # Returns
+    ///
+    /// Location for the external code jump, if any
+    fn exec_lib(
+        &self,
+        entrypoint: u16,
+        registers: &mut CoreRegs,
+        context: &Isa::Context<'_>,
+        preemption: Option<&mut Preemption>,
+    ) -> Option<LibSite>;
+}
+
+impl<Isa> ExecLib<Isa> for Lib
+where
+    Isa: InstructionSet,
+{
+    #[inline]
+    fn exec_lib(
+        &self,
+        entrypoint: u16,
+        registers: &mut CoreRegs,
+        context: &Isa::Context<'_>,
+        preemption: Option<&mut Preemption>,
+    ) -> Option<LibSite> {
+        self.exec_inner::<Isa>(entrypoint, registers, context, preemption)
+    }
+}
+
+impl<Extension> ExecLib<Instr<Extension>> for DecodedLib<Extension>
+where
+    Extension: InstructionSet,
+{
+    #[inline]
+    fn exec_lib(
+        &self,
+        entrypoint: u16,
+        registers: &mut CoreRegs,
+        context: &Extension::Context<'_>,
+        preemption: Option<&mut Preemption>,
+    ) -> Option<LibSite> {
+        self.exec_inner(entrypoint, registers, context, preemption)
+    }
+}
 
 /// Alu virtual machine providing single-core execution environment
 #[derive(Debug, Default)]
@@ -51,6 +307,11 @@ where
     /// Constructs new virtual machine instance.
     pub fn new() -> Self { Self { registers: Box::default(), phantom: Default::default() } }
 
+    /// Constructs new virtual machine instance with the provided core configuration.
+    pub fn with(config: CoreConfig) -> Self {
+        Self { registers: Box::new(CoreRegs::with(config)), phantom: Default::default() }
+    }
+
     /// Executes the program starting from the provided entry point.
     ///
     /// # Returns
@@ -62,10 +323,95 @@ where
         lib_resolver: impl Fn(LibId) -> Option<&'prog Lib>,
         context: &Isa::Context<'_>,
     ) -> bool {
+        self.exec_inner(entry_point, lib_resolver, context, None, None)
+    }
+
+    /// Executes the program starting from the provided entry point, invoking preemption callback
+    /// each time the program execution has spent the amount of fuel specified in `preemption`.
+    ///
+    /// # Returns
+    ///
+    /// Value of the `st0` register at the end of the program execution.
+    pub fn exec_preemptible<'prog>(
+        &mut self,
+        entry_point: LibSite,
+        lib_resolver: impl Fn(LibId) -> Option<&'prog Lib>,
+        context: &Isa::Context<'_>,
+        preemption: &mut Preemption,
+    ) -> bool {
+        self.exec_inner(entry_point, lib_resolver, context, Some(preemption), None)
+    }
+
+    /// Executes the program starting from the provided entry point in the same way as
+    /// [`Vm::exec`] or, if `preemption` is provided, [`Vm::exec_preemptible`]. Unlike them, the
+    /// library resolver may return any code implementing [`ExecLib`], like a [`DecodedLib`], by
+    /// reference or through another pointer type.
+    ///
+    /// # Returns
+    ///
+    /// Value of the `st0` register at the end of the program execution.
+    pub fn exec_with<L>(
+        &mut self,
+        entry_point: LibSite,
+        lib_resolver: impl Fn(LibId) -> Option<L>,
+        context: &Isa::Context<'_>,
+        preemption: Option<&mut Preemption>,
+    ) -> bool
+    where
+        L: Deref,
+        L::Target: ExecLib<Isa>,
+    {
+        self.exec_inner(entry_point, lib_resolver, context, preemption, None)
+    }
+
+    /// Executes the program starting from the provided entry point, collecting execution
+    /// statistics of each of the libraries into the `profiler`. If `preemption` is provided,
+    /// invokes its callback in the same way as [`Vm::exec_preemptible`].
+    ///
+    /// # Returns
+    ///
+    /// Value of the `st0` register at the end of the program execution.
+    pub fn exec_profiled<L>(
+        &mut self,
+        entry_point: LibSite,
+        lib_resolver: impl Fn(LibId) -> Option<L>,
+        context: &Isa::Context<'_>,
+        preemption: Option<&mut Preemption>,
+        profiler: &mut Profiler,
+    ) -> bool
+    where
+        L: Deref,
+        L::Target: ExecLib<Isa>,
+    {
+        self.exec_inner(entry_point, lib_resolver, context, preemption, Some(profiler))
+    }
+
+    fn exec_inner<L>(
+        &mut self,
+        entry_point: LibSite,
+        lib_resolver: impl Fn(LibId) -> Option<L>,
+        context: &Isa::Context<'_>,
+        mut preemption: Option<&mut Preemption>,
+        mut profiler: Option<&mut Profiler>,
+    ) -> bool
+    where
+        L: Deref,
+        L::Target: ExecLib<Isa>,
+    {
         let mut call = Some(entry_point);
         while let Some(ref mut site) = call {
             if let Some(lib) = lib_resolver(site.lib) {
-                call = lib.exec::<Isa>(site.pos, &mut self.registers, context);
+                let (ic0, ca0) = (self.registers.instr_count(), self.registers.complexity_acc());
+                let id = site.lib;
+                call =
+                    lib.exec_lib(site.pos, &mut self.registers, context, preemption.as_deref_mut());
+                if let Some(profiler) = profiler.as_deref_mut() {
+                    profiler.record(
+                        id,
+                        self.registers.instr_count() - ic0,
+                        self.registers.complexity_acc() - ca0,
+                    );
+                }
             } else if let Some(pos) = site.pos.checked_add(1) {
                 site.pos = pos;
             } else {
@@ -74,4 +420,414 @@ where
         }
         self.registers.st0
     }
+
+    /// Executes the program starting from the provided entry point once per each chunk of the
+    /// `input`, which is placed into the string registers according to the `binding`.
+    ///
+    /// Registers are preserved between the runs, such that the program can accumulate the state
+    /// of the processed input; the complexity limit applies to the whole input. The streaming
+    /// stops once the program fails.
+    ///
+    /// NB: The ISA does not provide incremental hashing instructions yet, thus a digest of the
+    ///     whole input can't be computed by the program. Until such instructions are added, the
+    ///     program has to combine per-chunk results itself, for instance by hashing each chunk
+    ///     together with the digest of the previous chunks.
+    ///
+    /// # Returns
+    ///
+    /// Value of the `st0` register at the end of the last program execution, or error if a chunk
+    /// can't fit the string register. In the latter case the input is not processed further.
+    pub fn exec_streamed<L, C>(
+        &mut self,
+        entry_point: LibSite,
+        lib_resolver: impl Fn(LibId) -> Option<L>,
+        context: &Isa::Context<'_>,
+        binding: StreamBinding,
+        input: impl IntoIterator<Item = C>,
+    ) -> Result<bool, StreamError>
+    where
+        L: Deref,
+        L::Target: ExecLib<Isa>,
+        C: AsRef<[u8]>,
+    {
+        let mut input = input.into_iter().peekable();
+        let mut chunk = 0u32;
+        while let Some(data) = input.next() {
+            let data = data.as_ref();
+            if data.len() > STREAM_CHUNK_MAX_LEN {
+                return Err(StreamError::ChunkTooLarge { chunk, len: data.len() });
+            }
+            let last = input.peek().is_none();
+            self.registers.set_s(binding.chunk_reg(chunk), Some(ByteStr::with(data)));
+            self.registers.set_n(RegA::A32, binding.index, Number::from(chunk));
+            self.registers.set_n(RegA::A8, binding.index, Number::from(last as u8));
+            if !self.exec_inner(entry_point, &lib_resolver, context, None, None) {
+                return Ok(false);
+            }
+            if !last {
+                chunk = chunk.checked_add(1).ok_or(StreamError::TooManyChunks)?;
+            }
+        }
+        Ok(self.registers.st0)
+    }
+
+    /// Executes the program starting from the provided entry point in the same way as
+    /// [`Vm::exec`], isolating the host from any panic happening in the interpreter or in the
+    /// cryptographic backends.
+    ///
+    /// If a panic happens, the state of the registers is unspecified, except `st0`, which is set to
+    /// `false`.
+    ///
+    /// # Returns
+    ///
+    /// Value of the `st0` register at the end of the program execution, or
+    /// [`ExecError::InternalError`] with the panic message.
+    #[cfg(feature = "sandbox")]
+    pub fn exec_sandboxed<'prog>(
+        &mut self,
+        entry_point: LibSite,
+        lib_resolver: impl Fn(LibId) -> Option<&'prog Lib>,
+        context: &Isa::Context<'_>,
+    ) -> Result<bool, ExecError> {
+        use std::panic::{catch_unwind, AssertUnwindSafe};
+
+        catch_unwind(AssertUnwindSafe(|| self.exec(entry_point, lib_resolver, context))).map_err(
+            |payload| {
+                self.registers.st0 = false;
+                let msg = if let Some(msg) = payload.downcast_ref::<&str>() {
+                    msg.to_string()
+                } else if let Some(msg) = payload.downcast_ref::<String>() {
+                    msg.clone()
+                } else {
+                    String::from("unknown panic")
+                };
+                ExecError::InternalError(msg)
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use core::cell::{Cell, RefCell};
+
+    use super::*;
+    use crate::data::{MaybeNumber, Number, Step};
+    use crate::isa::{
+        ArithmeticOp, CmpOp, ControlFlowOp, IntFlags, MoveOp, NoneEqFlag, PutOp, SignFlag,
+    };
+    use crate::reg::{Reg32, RegA, RegS};
+
+    const LOOPS: u64 = 10;
+
+    fn looped_lib() -> Lib {
+        let put = |idx, val: u64| {
+            Instr::Put(PutOp::PutA(RegA::A64, idx, Box::new(MaybeNumber::from(Number::from(val)))))
+        };
+        let mut code: Vec<Instr> = vec![put(Reg32::Reg0, 0), put(Reg32::Reg1, LOOPS)];
+        let start = Lib::assemble(&code).unwrap().code.len() as u16;
+        code.extend([
+            Instr::Arithmetic(ArithmeticOp::Stp(RegA::A64, Reg32::Reg0, Step::with(1))),
+            Instr::Cmp(CmpOp::LtA(SignFlag::Unsigned, RegA::A64, Reg32::Reg0, Reg32::Reg1)),
+            Instr::ControlFlow(ControlFlowOp::Jif(start)),
+            // Loop exits once the comparison fails, so the status must be restored
+            Instr::Cmp(CmpOp::StInv),
+            Instr::ControlFlow(ControlFlowOp::Ret),
+        ]);
+        Lib::assemble(&code).unwrap()
+    }
+
+    #[test]
+    fn profiler_hook() {
+        let lib = looped_lib();
+        let id = lib.id();
+        let hot = RefCell::new(Vec::new());
+        let mut profiler = Profiler::with(NonZeroU64::new(2).unwrap(), |id, stats| {
+            hot.borrow_mut().push((id, *stats))
+        });
+
+        let mut vm = Vm::<Instr>::new();
+        assert!(vm.exec_profiled(
+            LibSite::with(0, id),
+            |lib_id| (lib_id == id).then_some(&lib),
+            &(),
+            None,
+            &mut profiler
+        ));
+        let single = *profiler.stats(id).unwrap();
+        assert_eq!(single.calls, 1);
+        assert_eq!(single.instructions, vm.registers.instr_count());
+        assert_eq!(single.complexity, vm.registers.complexity_acc());
+
+        for _ in 0..2 {
+            let mut vm = Vm::<Instr>::new();
+            vm.exec_profiled(
+                LibSite::with(0, id),
+                |lib_id| (lib_id == id).then_some(&lib),
+                &(),
+                None,
+                &mut profiler,
+            );
+        }
+        assert_eq!(profiler.stats(id).unwrap().calls, 3);
+        assert_eq!(hot.borrow().as_slice(), &[(id, LibStats {
+            calls: 2,
+            instructions: single.instructions * 2,
+            complexity: single.complexity * 2,
+        })]);
+
+        assert_eq!(profiler.reset(id).map(|stats| stats.calls), Some(3));
+        for _ in 0..2 {
+            let mut vm = Vm::<Instr>::new();
+            vm.exec_profiled(
+                LibSite::with(0, id),
+                |lib_id| (lib_id == id).then_some(&lib),
+                &(),
+                None,
+                &mut profiler,
+            );
+        }
+        assert_eq!(hot.borrow().len(), 2);
+    }
+
+    #[test]
+    fn profiled_preemptible() {
+        let lib = looped_lib();
+        let id = lib.id();
+        let mut profiler = Profiler::with(NonZeroU64::new(1).unwrap(), |_, _| {});
+        let checks = Cell::new(0u64);
+        let mut preemption =
+            Preemption::with(FuelUnit::Instructions, NonZeroU64::new(4).unwrap(), |_| {
+                checks.set(checks.get() + 1);
+                checks.get() < 3
+            });
+
+        let mut vm = Vm::<Instr>::new();
+        assert!(!vm.exec_profiled(
+            LibSite::with(0, id),
+            |lib_id| (lib_id == id).then_some(&lib),
+            &(),
+            Some(&mut preemption),
+            &mut profiler
+        ));
+        assert_eq!(checks.get(), 3);
+        assert_eq!(vm.registers.instr_count(), 12);
+        assert_eq!(profiler.stats(id).unwrap().instructions, 12);
+    }
+
+    fn exec_preemptible(unit: FuelUnit, period: u64, halt_at: usize) -> (bool, Vec<u64>, Vm) {
+        let lib = looped_lib();
+        let id = lib.id();
+        let seen = RefCell::new(Vec::new());
+        let mut preemption = Preemption::with(unit, NonZeroU64::new(period).unwrap(), |regs| {
+            seen.borrow_mut().push(match unit {
+                FuelUnit::Instructions => regs.instr_count(),
+                FuelUnit::Complexity => regs.complexity_acc(),
+            });
+            seen.borrow().len() < halt_at
+        });
+        let mut vm = Vm::<Instr>::new();
+        let st0 = vm.exec_preemptible(
+            LibSite::with(0, id),
+            |lib_id| (lib_id == id).then_some(&lib),
+            &(),
+            &mut preemption,
+        );
+        drop(preemption);
+        (st0, seen.into_inner(), vm)
+    }
+
+    #[test]
+    fn preemption_instructions() {
+        let (st0, seen, vm) = exec_preemptible(FuelUnit::Instructions, 4, usize::MAX);
+        assert!(st0);
+        let total = vm.registers.instr_count();
+        assert_eq!(total, 2 + LOOPS * 3 + 2);
+        assert_eq!(seen, (1..=total / 4).map(|no| no * 4).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn preemption_halt() {
+        let (st0, seen, vm) = exec_preemptible(FuelUnit::Instructions, 4, 2);
+        assert!(!st0);
+        assert_eq!(seen, vec![4, 8]);
+        assert_eq!(vm.registers.instr_count(), 8);
+    }
+
+    #[test]
+    fn preemption_complexity() {
+        let (_, per_instr, vm) = exec_preemptible(FuelUnit::Complexity, 1, usize::MAX);
+        let total = vm.registers.complexity_acc();
+        // Each instruction spends more than a single unit of fuel, but the callback is invoked
+        // once per preemption point, which is rounded up to the next period boundary
+        assert!(total > vm.registers.instr_count());
+        assert_eq!(per_instr.len() as u64, vm.registers.instr_count());
+        assert_eq!(per_instr.last(), Some(&total));
+
+        let period = per_instr[0] * 3 / 2;
+        let (st0, seen, _) = exec_preemptible(FuelUnit::Complexity, period, usize::MAX);
+        assert!(st0);
+        assert!(!seen.is_empty() && (seen.len() as u64) <= total / period);
+        assert!(seen[0] >= period);
+        for pair in seen.windows(2) {
+            assert!(pair[1] / period > pair[0] / period, "{:?}", seen);
+        }
+        // Points are taken from the complexities accumulated after each of the instructions
+        assert!(seen.iter().all(|fuel| per_instr.contains(fuel)));
+    }
+
+    #[test]
+    fn decoded_lib_resolver() {
+        let lib = looped_lib();
+        let id = lib.id();
+        let mut decoded = DecodedLib::<ReservedOp>::with(&lib);
+        assert_eq!(decoded.fuse(), 1);
+
+        let mut vm = Vm::<Instr>::new();
+        assert!(vm.exec(LibSite::with(0, id), |lib_id| (lib_id == id).then_some(&lib), &()));
+        let mut decoded_vm = Vm::<Instr>::new();
+        assert!(decoded_vm.exec_with(
+            LibSite::with(0, id),
+            |lib_id| (lib_id == id).then_some(&decoded),
+            &(),
+            None
+        ));
+        assert_eq!(decoded_vm.registers.instr_count(), vm.registers.instr_count());
+        assert_eq!(
+            decoded_vm.registers.get_n(RegA::A64, Reg32::Reg0),
+            MaybeNumber::from(Number::from(LOOPS))
+        );
+    }
+
+    #[test]
+    fn stream_binding_window() {
+        assert_eq!(StreamBinding::with(RegS::from(0), 0, Reg32::Reg0), None);
+        assert_eq!(StreamBinding::with(RegS::from(0), 17, Reg32::Reg0), None);
+        let binding = StreamBinding::with(RegS::from(14), 4, Reg32::Reg0).unwrap();
+        let regs = (0..6).map(|chunk| u8::from(binding.chunk_reg(chunk))).collect::<Vec<_>>();
+        assert_eq!(regs, vec![14, 15, 0, 1, 14, 15]);
+    }
+
+    #[test]
+    fn stream_rotation() {
+        // Succeeds for all chunks except the last one
+        let lib = Lib::assemble::<Instr>(&[
+            Instr::Put(PutOp::PutA(
+                RegA::A8,
+                Reg32::Reg1,
+                Box::new(MaybeNumber::from(Number::from(0u8))),
+            )),
+            Instr::Cmp(CmpOp::EqA(NoneEqFlag::NonEqual, RegA::A8, Reg32::Reg0, Reg32::Reg1)),
+            Instr::ControlFlow(ControlFlowOp::Ret),
+        ])
+        .unwrap();
+        let id = lib.id();
+        let binding = StreamBinding::with(RegS::from(15), 2, Reg32::Reg0).unwrap();
+        let input: [&[u8]; 3] = [b"first", b"second", b"third"];
+
+        let mut vm = Vm::<Instr>::new();
+        let res = vm.exec_streamed(
+            LibSite::with(0, id),
+            |lib_id| (lib_id == id).then_some(&lib),
+            &(),
+            binding,
+            input,
+        );
+        assert_eq!(res, Ok(false));
+        assert_eq!(vm.registers.instr_count(), 9);
+        assert_eq!(vm.registers.get_n(RegA::A32, Reg32::Reg0), Number::from(2u32).into());
+        assert_eq!(vm.registers.get_n(RegA::A8, Reg32::Reg0), Number::from(1u8).into());
+        assert_eq!(vm.registers.get_s(RegS::from(15)).map(ByteStr::as_ref), Some(&b"third"[..]));
+        assert_eq!(vm.registers.get_s(RegS::from(0)).map(ByteStr::as_ref), Some(&b"second"[..]));
+    }
+
+    #[test]
+    fn stream_chunk_too_large() {
+        let lib = Lib::assemble::<Instr>(&[Instr::ControlFlow(ControlFlowOp::Ret)]).unwrap();
+        let id = lib.id();
+        let binding = StreamBinding::with(RegS::from(0), 1, Reg32::Reg0).unwrap();
+        let input = vec![vec![0u8; 16], vec![0u8; STREAM_CHUNK_MAX_LEN + 1], vec![0u8; 16]];
+
+        let mut vm = Vm::<Instr>::new();
+        let res = vm.exec_streamed(
+            LibSite::with(0, id),
+            |lib_id| (lib_id == id).then_some(&lib),
+            &(),
+            binding,
+            input,
+        );
+        assert_eq!(
+            res,
+            Err(StreamError::ChunkTooLarge { chunk: 1, len: STREAM_CHUNK_MAX_LEN + 1 })
+        );
+        assert_eq!(vm.registers.instr_count(), 1);
+    }
+
+    #[test]
+    fn halt_on_uninit() {
+        let strict = CoreConfig { halt_on_uninit: true };
+        let exec = |code: &[Instr]| {
+            let lib = Lib::assemble(code).unwrap();
+            let id = lib.id();
+            let mut vm = Vm::<Instr>::with(strict);
+            let st0 = vm.exec(LibSite::with(0, id), |lib_id| (lib_id == id).then_some(&lib), &());
+            (st0, vm.registers.instr_count())
+        };
+
+        // Instructions handling uninitialized registers don't halt the program
+        assert_eq!(
+            exec(&[
+                Instr::Cmp(CmpOp::IfNA(RegA::A8, Reg32::Reg0)),
+                Instr::Move(MoveOp::SwpA(RegA::A8, Reg32::Reg0, Reg32::Reg1)),
+                Instr::Move(MoveOp::MovA(RegA::A8, Reg32::Reg0, Reg32::Reg1)),
+                Instr::Cmp(CmpOp::EqA(NoneEqFlag::Equal, RegA::A8, Reg32::Reg0, Reg32::Reg1)),
+                Instr::ControlFlow(ControlFlowOp::Ret),
+            ]),
+            (true, 5)
+        );
+        // Arithmetic on uninitialized registers halts before the instruction is executed
+        assert_eq!(
+            exec(&[
+                Instr::Cmp(CmpOp::IfNA(RegA::A8, Reg32::Reg0)),
+                Instr::Arithmetic(ArithmeticOp::AddA(
+                    IntFlags::unsigned_checked(),
+                    RegA::A8,
+                    Reg32::Reg0,
+                    Reg32::Reg1,
+                )),
+                Instr::ControlFlow(ControlFlowOp::Ret),
+            ]),
+            (false, 1)
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "sandbox")]
+    fn sandboxed_panic() {
+        use crate::reg::RegF;
+
+        // Conversion into tapered floats is not implemented and panics
+        let lib = Lib::assemble::<Instr>(&[
+            Instr::Move(MoveOp::CnvF(RegF::F128, Reg32::Reg0, RegF::F512, Reg32::Reg0)),
+            Instr::ControlFlow(ControlFlowOp::Ret),
+        ])
+        .unwrap();
+        let id = lib.id();
+
+        let mut vm = Vm::<Instr>::new();
+        vm.registers.set_n(RegF::F128, Reg32::Reg0, "1.0".parse::<MaybeNumber>().unwrap());
+        let res =
+            vm.exec_sandboxed(LibSite::with(0, id), |lib_id| (lib_id == id).then_some(&lib), &());
+        assert!(
+            matches!(res, Err(ExecError::InternalError(ref msg)) if msg.contains("tapered float")),
+            "{:?}",
+            res
+        );
+        assert!(!vm.registers.st0);
+
+        let mut vm = Vm::<Instr>::new();
+        let res =
+            vm.exec_sandboxed(LibSite::with(0, id), |lib_id| (lib_id == id).then_some(&lib), &());
+        assert_eq!(res, Ok(true));
+    }
 }